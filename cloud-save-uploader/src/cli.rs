@@ -0,0 +1,120 @@
+use std::env;
+use std::sync::mpsc;
+use std::time::Duration;
+
+use vscs_core::game_watcher::{GameWatcher, WatcherEvent};
+use vscs_core::manifest_info::get_manifest_info;
+use vscs_core::save_watcher::SaveWatcher;
+use vscs_core::scheduler::Scheduler;
+use vscs_core::sync_plan::SyncDirection;
+use vscs_core::upload_core::{preview_download, preview_upload};
+use vscs_core::verify::{verify_backup, VerifyOptions};
+use vscs_core::world_lock::LeaseKeeper;
+use vscs_core::Error;
+
+/// Whether the program was started with `--headless`, e.g. next to a
+/// dedicated server without a desktop.
+pub fn headless_requested() -> bool {
+    env::args().skip(1).any(|arg| arg == "--headless")
+}
+
+/// With `--dry-run`, the directions to plan: those named after the flag
+/// (`upload`, `download`), or both.
+pub fn dry_run_requested() -> Option<Vec<SyncDirection>> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let position = args.iter().position(|arg| arg == "--dry-run")?;
+    let directions: Vec<SyncDirection> = args[position + 1..]
+        .iter()
+        .map_while(|arg| match arg.as_str() {
+            "upload" => Some(SyncDirection::Upload),
+            "download" => Some(SyncDirection::Download),
+            _ => None,
+        })
+        .collect();
+    if directions.is_empty() {
+        Some(vec![SyncDirection::Upload, SyncDirection::Download])
+    } else {
+        Some(directions)
+    }
+}
+
+/// Prints what syncing the current folder bucket in `directions` would do,
+/// without changing anything.
+pub fn run_dry_run(directions: &[SyncDirection]) -> Result<(), Error> {
+    let folder_bucket = get_manifest_info()?.current_used_bucket;
+    for direction in directions {
+        let plan = match direction {
+            SyncDirection::Upload => preview_upload(folder_bucket.clone())?,
+            SyncDirection::Download => preview_download(folder_bucket.clone())?,
+        };
+        print!("{}", plan);
+    }
+    Ok(())
+}
+
+/// With `--verify`, how to check the backup, from the words after the flag:
+/// `download` to hash every object, `open-worlds` to open worlds with
+/// SQLite, `sample N` to check N random objects.
+pub fn verify_requested() -> Option<VerifyOptions> {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let position = args.iter().position(|arg| arg == "--verify")?;
+    let mut options = VerifyOptions::default();
+    let mut rest = args[position + 1..].iter();
+    while let Some(arg) = rest.next() {
+        match arg.as_str() {
+            "download" => options.download = true,
+            "open-worlds" => options.open_worlds = true,
+            "sample" => options.sample = rest.next().and_then(|count| count.parse().ok()),
+            _ => break,
+        }
+    }
+    Some(options)
+}
+
+/// Verifies the backup of the current folder bucket and prints the report.
+/// Returns whether nothing was wrong.
+pub fn run_verify(options: VerifyOptions) -> Result<bool, Error> {
+    let folder_bucket = get_manifest_info()?.current_used_bucket;
+    let report = verify_backup(folder_bucket, options, None)?;
+    print!("{}", report);
+    Ok(report.is_healthy())
+}
+
+/// Runs the scheduler and the auto sync watchers without a window, logging
+/// what they do to stdout. Only returns on failure.
+pub fn run_headless() -> Result<(), Error> {
+    let program_data = get_manifest_info()?;
+    let folder_bucket = program_data.current_used_bucket;
+    let settings = program_data.auto_sync;
+    let (events_tx, events_rx) = mpsc::channel();
+
+    let _scheduler = Scheduler::start(events_tx.clone());
+    let _lease_keeper = LeaseKeeper::start(program_data.locking.lease());
+    let _game_watcher = settings.upload_on_exit.then(|| {
+        GameWatcher::start(
+            folder_bucket.clone(),
+            Duration::from_secs(settings.upload_delay_secs),
+            events_tx.clone(),
+        )
+    });
+    let _save_watcher = if settings.watch_saves {
+        Some(SaveWatcher::start(
+            folder_bucket.clone(),
+            Duration::from_secs(settings.quiet_period_secs),
+            events_tx.clone(),
+        )?)
+    } else {
+        None
+    };
+    drop(events_tx);
+
+    println!("Running headless for folder {}", folder_bucket);
+    while let Ok(event) = events_rx.recv() {
+        match event {
+            WatcherEvent::UploadProgress(_) => {}
+            WatcherEvent::UploadFailed { error } => eprintln!("Upload failed: {}", error),
+            other => println!("{:?}", other),
+        }
+    }
+    Err(Error::other("all background tasks stopped"))
+}
@@ -1,25 +1,59 @@
-mod upload_core;
-mod helper_functions;
-mod manifest_info;
-use crate::manifest_info::{get_manifest_info, manifest_status_message};
+mod cli;
+use vscs_core::cancel::CancelToken;
+use vscs_core::config::{
+    config_file_path, known_folder_buckets, load_config, set_config, AppConfig, Encryption, StorageProfile,
+    StorageProvider, DEFAULT_PROFILE,
+};
+use vscs_core::device::{device_identity, set_device_name};
+use vscs_core::game_process::{game_status, GameStatus};
+use vscs_core::game_watcher::{launch_with_sync, GameWatcher, WatcherEvent};
+use vscs_core::hooks::HookSettings;
+use vscs_core::profile::{DataProfile, ServerProfile};
+use vscs_core::save_watcher::SaveWatcher;
+use vscs_core::scheduler::{Schedule, ScheduleRule, ScheduledAction, Scheduler};
+use vscs_core::manifest_info::{
+    get_manifest_info, manifest_status_message, set_auto_sync, set_hooks, set_profile, set_schedules,
+    set_scope_enabled, set_sync_filter, AutoSyncSettings, VintageProgramData,
+};
+use vscs_core::sync_filter::SyncFilter;
+use vscs_core::sync_plan::{PlanAction, PlanReason, SyncDirection, SyncPlan};
+use vscs_core::sync_scope::SyncScope;
+use vscs_core::verify::{verify_backup, VerifyOptions, VerifyReport};
+use vscs_core::world_lock::LeaseKeeper;
+use vscs_core::Error;
 
-use crate::upload_core::{download_save, upload_save, UploadProgress};
-use dotenvy::dotenv;
+use vscs_core::progress::TransferProgress;
+use vscs_core::upload_core::{
+    download_save, preview_download, preview_upload, test_connection, upload_save, DownloadOptions,
+    UploadOptions,
+};
 use eframe::{egui, App, CreationContext};
 use std::sync::mpsc::{self, Receiver, Sender};
 use std::thread;
+use std::time::{Duration, Instant};
+
+/// How often the game process state is re-checked.
+const GAME_STATUS_INTERVAL: Duration = Duration::from_secs(5);
 
 enum UploadEvent {
+    /// The upload transfers files and waits for the plan to be confirmed.
+    Planned(SyncPlan),
     Started,
     Finished,
     Failed(String),
-    Progress(UploadProgress),
+    Cancelled,
+    Progress(TransferProgress),
 }
 
 enum DownloadEvent {
     Started,
+    Progress(TransferProgress),
+    /// The download transfers files or has warnings and waits for the plan
+    /// to be confirmed.
+    Planned(SyncPlan),
     Finished,
     Failed(String),
+    Cancelled,
 }
 
 struct CloudApp {
@@ -39,6 +73,74 @@ struct CloudApp {
     downloading: bool,
     download_receiver: Receiver<DownloadEvent>,
     download_sender: Sender<DownloadEvent>,
+    // Plans waiting for the user to confirm or cancel.
+    upload_plan: Option<SyncPlan>,
+    download_plan: Option<SyncPlan>,
+    // Stop the transfers started with the buttons while they run.
+    upload_cancel: Option<CancelToken>,
+    download_cancel: Option<CancelToken>,
+
+    // Sync rule editor state; reloaded whenever the bucket or scope changes.
+    filter_bucket: Option<(String, SyncScope)>,
+    filter_scope: SyncScope,
+    filter_include: String,
+    filter_exclude: String,
+    filter_status: Option<String>,
+
+    game_status: GameStatus,
+    game_status_checked: Option<Instant>,
+
+    auto_sync: AutoSyncSettings,
+    auto_sync_status: Option<String>,
+    // Running while `auto_sync.upload_on_exit` is set.
+    game_watcher: Option<GameWatcher>,
+    // Running while `auto_sync.watch_saves` is set.
+    save_watcher: Option<SaveWatcher>,
+    watcher_receiver: Receiver<WatcherEvent>,
+    watcher_sender: Sender<WatcherEvent>,
+
+    _scheduler: Scheduler,
+    _lease_keeper: LeaseKeeper,
+    // New schedule form.
+    schedule_action: ScheduledAction,
+    schedule_use_cron: bool,
+    schedule_value: String,
+    schedule_while_running: bool,
+    schedule_status: Option<String>,
+
+    // Data profile editor; `server_profile` is kept while the client
+    // profile is selected so switching back does not lose it.
+    profile_server: bool,
+    server_profile: ServerProfile,
+    pre_backup_command: String,
+    profile_status: Option<String>,
+
+    // Hook commands being edited; empty means no hook.
+    hook_commands: [String; 4],
+    hooks_status: Option<String>,
+
+    device_name: String,
+    device_status: Option<String>,
+
+    // Storage settings being edited, without the application key; an empty
+    // key field keeps the stored one.
+    config: AppConfig,
+    profiles_with_key: Vec<String>,
+    // Index of the profile shown in the editor.
+    selected_profile: usize,
+    new_folder_bucket: String,
+    // Folder bucket and manifest key of a world binding being added.
+    new_world_binding: (String, String),
+    config_status: Option<String>,
+    // Result of the running connection test.
+    connection_test: Option<Receiver<Result<(), Error>>>,
+
+    // Backup verification: how to check, the running check and its report.
+    verify_options: VerifyOptions,
+    verify_sample: bool,
+    verify_sample_size: usize,
+    verify_run: Option<(Receiver<Result<VerifyReport, Error>>, CancelToken)>,
+    verify_status: Option<String>,
 }
 
 impl CloudApp {
@@ -48,9 +150,18 @@ impl CloudApp {
         let initial_status = manifest_status_message().unwrap();
         let manifest = get_manifest_info().unwrap();
         let folder_bucket = manifest.current_used_bucket;
+        let (watcher_sender, watcher_receiver) = mpsc::channel();
+        let (config, config_status) = match load_config() {
+            Ok(config) => (config, None),
+            Err(err) => (AppConfig::default(), Some(error_text(&err))),
+        };
+        let (profile_server, server_profile) = match manifest.profile {
+            DataProfile::Client => (false, ServerProfile::default()),
+            DataProfile::Server(server) => (true, server),
+        };
 
         CloudApp {
-            folder_bucket: folder_bucket,
+            folder_bucket,
             upload_status: initial_status,
             upload_progress: 0.0,
             upload_error: None,
@@ -63,12 +174,691 @@ impl CloudApp {
             downloading: false,
             download_receiver,
             download_sender,
+            upload_plan: None,
+            download_plan: None,
+            upload_cancel: None,
+            download_cancel: None,
+            filter_bucket: None,
+            filter_scope: SyncScope::Saves,
+            filter_include: String::new(),
+            filter_exclude: String::new(),
+            filter_status: None,
+            game_status: GameStatus::default(),
+            game_status_checked: None,
+            auto_sync: manifest.auto_sync,
+            auto_sync_status: None,
+            game_watcher: None,
+            save_watcher: None,
+            _scheduler: Scheduler::start(watcher_sender.clone()),
+            _lease_keeper: LeaseKeeper::start(manifest.locking.lease()),
+            watcher_receiver,
+            watcher_sender,
+            schedule_action: ScheduledAction::Upload,
+            schedule_use_cron: false,
+            schedule_value: "30".to_string(),
+            schedule_while_running: false,
+            schedule_status: None,
+            profile_server,
+            pre_backup_command: server_profile.pre_backup_command.clone().unwrap_or_default(),
+            server_profile,
+            profile_status: None,
+            hook_commands: [
+                manifest.hooks.pre_upload,
+                manifest.hooks.post_upload,
+                manifest.hooks.pre_download,
+                manifest.hooks.post_download,
+            ]
+            .map(Option::unwrap_or_default),
+            hooks_status: None,
+            device_name: device_identity().map(|device| device.name).unwrap_or_default(),
+            device_status: None,
+            profiles_with_key: config.profiles_with_key(),
+            config: editable_config(&config),
+            selected_profile: 0,
+            new_folder_bucket: String::new(),
+            new_world_binding: (String::new(), String::new()),
+            config_status,
+            connection_test: None,
+            verify_options: VerifyOptions::default(),
+            verify_sample: false,
+            verify_sample_size: 20,
+            verify_run: None,
+            verify_status: None,
         }
+        .with_watchers()
+    }
+
+    fn with_watchers(mut self) -> Self {
+        self.apply_watchers();
+        self
+    }
+
+    /// Starts or stops the game and save watchers to match the auto sync
+    /// settings.
+    fn apply_watchers(&mut self) {
+        self.game_watcher = self.auto_sync.upload_on_exit.then(|| {
+            GameWatcher::start(
+                self.folder_bucket.clone(),
+                Duration::from_secs(self.auto_sync.upload_delay_secs),
+                self.watcher_sender.clone(),
+            )
+        });
+
+        self.save_watcher = None;
+        if self.auto_sync.watch_saves {
+            match SaveWatcher::start(
+                self.folder_bucket.clone(),
+                Duration::from_secs(self.auto_sync.quiet_period_secs),
+                self.watcher_sender.clone(),
+            ) {
+                Ok(watcher) => self.save_watcher = Some(watcher),
+                Err(err) => self.auto_sync_status = Some(err.to_string()),
+            }
+        }
+    }
+
+    fn auto_sync_editor(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Auto sync").show(ui, |ui| {
+            let mut changed = ui
+                .checkbox(&mut self.auto_sync.upload_on_exit, "Upload after the game exits")
+                .changed();
+            ui.horizontal(|ui| {
+                ui.label("Wait before uploading (seconds):");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.auto_sync.upload_delay_secs).range(0..=3600))
+                    .changed();
+            });
+            changed |= ui
+                .checkbox(&mut self.auto_sync.download_before_launch, "Download latest before launch")
+                .changed();
+            changed |= ui
+                .checkbox(&mut self.auto_sync.watch_saves, "Upload changed worlds while playing")
+                .changed();
+            ui.horizontal(|ui| {
+                ui.label("Quiet period before uploading (seconds):");
+                changed |= ui
+                    .add(egui::DragValue::new(&mut self.auto_sync.quiet_period_secs).range(5..=3600))
+                    .changed();
+            });
+            if changed {
+                self.auto_sync_status = set_auto_sync(self.auto_sync.clone()).err().map(|err| err.to_string());
+                self.apply_watchers();
+            }
+
+            if ui.button("Launch Vintage Story").clicked() {
+                let tx = self.download_sender.clone();
+                let folder = self.folder_bucket.clone();
+                let settings = self.auto_sync.clone();
+                thread::spawn(move || {
+                    if settings.download_before_launch {
+                        let _ = tx.send(DownloadEvent::Started);
+                    }
+                    match launch_with_sync(folder, &settings) {
+                        Ok(()) if settings.download_before_launch => {
+                            let _ = tx.send(DownloadEvent::Finished);
+                        }
+                        Ok(()) => {}
+                        Err(err) => {
+                            let _ = tx.send(DownloadEvent::Failed(error_text(&err)));
+                        }
+                    }
+                });
+            }
+            if let Some(status) = &self.auto_sync_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn storage_editor(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Storage").show(ui, |ui| {
+            if let Ok(path) = config_file_path() {
+                ui.label(format!("Saved in {}", path.display()));
+            }
+
+            ui.horizontal(|ui| {
+                ui.label("Profiles:");
+                for (index, profile) in self.config.profiles.iter().enumerate() {
+                    ui.selectable_value(&mut self.selected_profile, index, profile.name.as_str());
+                }
+                if ui.button("Add profile").clicked() {
+                    self.config.profiles.push(StorageProfile {
+                        name: format!("profile-{}", self.config.profiles.len() + 1),
+                        ..StorageProfile::default()
+                    });
+                    self.selected_profile = self.config.profiles.len() - 1;
+                }
+                if self.config.profiles.len() > 1 && ui.button("Remove profile").clicked() {
+                    self.config.profiles.remove(self.selected_profile);
+                    self.selected_profile = 0;
+                }
+            });
+            ui.label("Folder buckets without a binding use the first profile.");
+            self.selected_profile = self.selected_profile.min(self.config.profiles.len() - 1);
+            let profile = &mut self.config.profiles[self.selected_profile];
+            let has_key = self.profiles_with_key.contains(&profile.name);
+            egui::Grid::new("storage_grid").num_columns(2).show(ui, |ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut profile.name);
+                ui.end_row();
+                let storage = &mut profile.storage;
+                ui.label("Provider:");
+                egui::ComboBox::from_id_salt("storage_provider")
+                    .selected_text(storage.provider.label())
+                    .show_ui(ui, |ui| {
+                        for provider in StorageProvider::ALL {
+                            ui.selectable_value(&mut storage.provider, provider, provider.label());
+                        }
+                    });
+                ui.end_row();
+                ui.label("Bucket:");
+                ui.text_edit_singleline(&mut storage.bucket);
+                ui.end_row();
+                ui.label("Region:");
+                let region_hint = storage.region();
+                ui.add(egui::TextEdit::singleline(&mut storage.region).hint_text(region_hint));
+                ui.end_row();
+                ui.label("Endpoint:");
+                let endpoint_hint = storage.endpoint().unwrap_or_else(|| "AWS".to_string());
+                ui.add(egui::TextEdit::singleline(&mut storage.endpoint).hint_text(endpoint_hint));
+                ui.end_row();
+                ui.label("Key ID:");
+                ui.text_edit_singleline(&mut storage.key_id);
+                ui.end_row();
+                ui.label("Application key:");
+                let key_hint = if has_key { "Stored; leave empty to keep it" } else { "" };
+                ui.add(egui::TextEdit::singleline(&mut storage.application_key).password(true).hint_text(key_hint));
+                ui.end_row();
+                ui.label("Prefix in the bucket:");
+                ui.add(egui::TextEdit::singleline(&mut profile.prefix).hint_text("None"));
+                ui.end_row();
+                ui.label("Encryption:");
+                ui.horizontal(|ui| {
+                    egui::ComboBox::from_id_salt("storage_encryption")
+                        .selected_text(encryption_label(&profile.encryption))
+                        .show_ui(ui, |ui| {
+                            for encryption in [
+                                Encryption::None,
+                                Encryption::Aes256,
+                                Encryption::Kms { key_id: String::new() },
+                            ] {
+                                let selected = std::mem::discriminant(&encryption)
+                                    == std::mem::discriminant(&profile.encryption);
+                                if ui.selectable_label(selected, encryption_label(&encryption)).clicked() && !selected {
+                                    profile.encryption = encryption;
+                                }
+                            }
+                        });
+                    if let Encryption::Kms { key_id } = &mut profile.encryption {
+                        ui.add(egui::TextEdit::singleline(key_id).hint_text("KMS key ID or ARN"));
+                    }
+                });
+                ui.end_row();
+            });
+            ui.checkbox(
+                &mut profile.storage.plaintext_key_fallback,
+                "Keep the key in the config file if the system keyring is unavailable",
+            );
+
+            let names: Vec<String> = self.config.profiles.iter().map(|profile| profile.name.clone()).collect();
+            ui.label("Folder buckets, their prefix and the profiles they are stored in (read from the first ticked):");
+            let mut removed = None;
+            egui::Grid::new("folder_buckets_grid").num_columns(4).show(ui, |ui| {
+                for (index, folder_bucket) in self.config.folder_buckets.iter().enumerate() {
+                    ui.label(folder_bucket);
+                    let prefix = self.config.prefixes.entry(folder_bucket.clone()).or_default();
+                    ui.add(egui::TextEdit::singleline(prefix).hint_text(folder_bucket.as_str()));
+                    let binding = self.config.bindings.entry(folder_bucket.clone()).or_default();
+                    ui.horizontal(|ui| profile_checkboxes(ui, &names, &mut binding.profiles));
+                    if ui.button("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                    ui.end_row();
+                }
+            });
+            if let Some(index) = removed {
+                let folder_bucket = self.config.folder_buckets.remove(index);
+                self.config.prefixes.remove(&folder_bucket);
+                self.config.bindings.remove(&folder_bucket);
+            }
+            ui.horizontal(|ui| {
+                ui.text_edit_singleline(&mut self.new_folder_bucket);
+                let name = self.new_folder_bucket.trim().to_string();
+                if ui.button("Add folder bucket").clicked()
+                    && !name.is_empty()
+                    && !self.config.folder_buckets.contains(&name)
+                {
+                    self.config.folder_buckets.push(name);
+                    self.new_folder_bucket.clear();
+                }
+            });
+
+            ui.label("Worlds stored apart from the rest of their folder bucket:");
+            let mut removed = None;
+            egui::Grid::new("world_bindings_grid").num_columns(4).show(ui, |ui| {
+                for (folder_bucket, binding) in &mut self.config.bindings {
+                    for (world, profiles) in &mut binding.worlds {
+                        ui.label(folder_bucket.as_str());
+                        ui.label(world.as_str());
+                        ui.horizontal(|ui| profile_checkboxes(ui, &names, profiles));
+                        if ui.button("Remove").clicked() {
+                            removed = Some((folder_bucket.clone(), world.clone()));
+                        }
+                        ui.end_row();
+                    }
+                }
+            });
+            if let Some((folder_bucket, world)) = removed
+                && let Some(binding) = self.config.bindings.get_mut(&folder_bucket)
+            {
+                binding.worlds.remove(&world);
+            }
+            ui.horizontal(|ui| {
+                let (folder_bucket, world) = &mut self.new_world_binding;
+                ui.add(egui::TextEdit::singleline(folder_bucket).hint_text("Folder bucket"));
+                ui.add(egui::TextEdit::singleline(world).hint_text("world.vcdbs"));
+                let (folder_bucket, world) = (folder_bucket.trim().to_string(), world.trim().to_string());
+                if ui.button("Add world").clicked() && !folder_bucket.is_empty() && !world.is_empty() {
+                    let binding = self.config.bindings.entry(folder_bucket).or_default();
+                    binding.worlds.entry(world).or_insert_with(|| vec![names[0].clone()]);
+                    self.new_world_binding = (String::new(), String::new());
+                }
+            });
+
+            ui.horizontal(|ui| {
+                ui.label("Attempts per request:");
+                ui.add(egui::DragValue::new(&mut self.config.retry.max_attempts).range(1..=10));
+                ui.label("First retry after (ms):");
+                ui.add(egui::DragValue::new(&mut self.config.retry.initial_delay_ms).range(0..=60_000));
+                ui.label("Longest wait (seconds):");
+                ui.add(egui::DragValue::new(&mut self.config.retry.max_delay_secs).range(1..=600));
+            });
+
+            ui.horizontal(|ui| {
+                let testing = self.connection_test.is_some();
+                if ui.add_enabled(!testing, egui::Button::new("Test connection")).clicked() {
+                    let (tx, rx) = mpsc::channel();
+                    let storage = self.config.profiles[self.selected_profile].storage.clone();
+                    thread::spawn(move || {
+                        let _ = tx.send(test_connection(storage));
+                    });
+                    self.connection_test = Some(rx);
+                    self.config_status = Some("Testing the connection...".to_string());
+                }
+                if ui.button("Save storage settings").clicked() {
+                    self.save_config();
+                }
+            });
+            if let Some(rx) = &self.connection_test {
+                match rx.try_recv() {
+                    Ok(result) => {
+                        self.config_status = Some(match result {
+                            Ok(()) => "Connected to the bucket".to_string(),
+                            Err(err) => error_text(&err),
+                        });
+                        self.connection_test = None;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => ui.ctx().request_repaint_after(Duration::from_millis(100)),
+                    Err(mpsc::TryRecvError::Disconnected) => self.connection_test = None,
+                }
+            }
+            if let Some(status) = &self.config_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn save_config(&mut self) {
+        self.config_status = Some(match set_config(self.config.clone()) {
+            Ok(()) => {
+                let saved = load_config().unwrap_or_else(|_| self.config.clone());
+                self.profiles_with_key = saved.profiles_with_key();
+                self.config = editable_config(&saved);
+                "Storage settings saved".to_string()
+            }
+            Err(err) => error_text(&err),
+        });
+    }
+
+    fn profile_editor(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Data profile").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.radio_value(&mut self.profile_server, false, "Game client");
+                ui.radio_value(&mut self.profile_server, true, "Dedicated server");
+            });
+            if self.profile_server {
+                ui.horizontal(|ui| {
+                    ui.label("Server data path:");
+                    ui.text_edit_singleline(&mut self.server_profile.data_path);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Pre-backup command:");
+                    ui.text_edit_singleline(&mut self.pre_backup_command);
+                });
+                ui.horizontal(|ui| {
+                    ui.label("Wait after the command (seconds):");
+                    ui.add(egui::DragValue::new(&mut self.server_profile.pre_backup_wait_secs).range(0..=600));
+                });
+            }
+
+            if ui.button("Save profile").clicked() {
+                let profile = if self.profile_server {
+                    let command = self.pre_backup_command.trim();
+                    DataProfile::Server(ServerProfile {
+                        pre_backup_command: (!command.is_empty()).then(|| command.to_string()),
+                        ..self.server_profile.clone()
+                    })
+                } else {
+                    DataProfile::Client
+                };
+                self.profile_status = Some(match set_profile(profile) {
+                    Ok(()) => {
+                        // The save watcher follows the Saves folder of the new profile.
+                        self.apply_watchers();
+                        "Profile saved".to_string()
+                    }
+                    Err(err) => err.to_string(),
+                });
+            }
+            if let Some(status) = &self.profile_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn device_editor(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("This device").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Name shown to your other devices:");
+                ui.text_edit_singleline(&mut self.device_name);
+                if ui.button("Rename").clicked() {
+                    self.device_status = set_device_name(&self.device_name).err().map(|err| err.to_string());
+                }
+            });
+            if let Some(status) = &self.device_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn hooks_editor(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Hooks").show(ui, |ui| {
+            ui.label("Shell commands run around syncs. A failing pre hook cancels the sync.");
+            let labels = ["Before upload:", "After upload:", "Before download:", "After download:"];
+            egui::Grid::new("hooks_grid").num_columns(2).show(ui, |ui| {
+                for (label, command) in labels.iter().zip(self.hook_commands.iter_mut()) {
+                    ui.label(*label);
+                    ui.text_edit_singleline(command);
+                    ui.end_row();
+                }
+            });
+
+            if ui.button("Save hooks").clicked() {
+                let [pre_upload, post_upload, pre_download, post_download] = self.hook_commands.clone().map(|command| {
+                    let command = command.trim().to_string();
+                    (!command.is_empty()).then_some(command)
+                });
+                let hooks = HookSettings {
+                    pre_upload,
+                    post_upload,
+                    pre_download,
+                    post_download,
+                };
+                self.hooks_status = Some(match set_hooks(hooks) {
+                    Ok(()) => "Hooks saved".to_string(),
+                    Err(err) => err.to_string(),
+                });
+            }
+            if let Some(status) = &self.hooks_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn verify_editor(&mut self, ui: &mut egui::Ui) {
+        egui::CollapsingHeader::new("Verify backup").show(ui, |ui| {
+            ui.label("Checks that what the manifests list is in the bucket and intact, and finds objects none lists.");
+            ui.checkbox(&mut self.verify_options.download, "Download the objects and compare their hashes");
+            ui.checkbox(&mut self.verify_options.open_worlds, "Open worlds with SQLite");
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.verify_sample, "Only check a random sample of");
+                ui.add_enabled(
+                    self.verify_sample,
+                    egui::DragValue::new(&mut self.verify_sample_size).range(1..=10_000),
+                );
+                ui.label("objects");
+            });
+
+            ui.horizontal(|ui| {
+                let running = self.verify_run.is_some();
+                if ui.add_enabled(!running, egui::Button::new("Verify")).clicked() {
+                    let cancel = CancelToken::new();
+                    let options = VerifyOptions {
+                        sample: self.verify_sample.then_some(self.verify_sample_size),
+                        cancel: cancel.clone(),
+                        ..self.verify_options.clone()
+                    };
+                    let folder = self.folder_bucket.clone();
+                    let (tx, rx) = mpsc::channel();
+                    thread::spawn(move || {
+                        let _ = tx.send(verify_backup(folder, options, None));
+                    });
+                    self.verify_run = Some((rx, cancel));
+                    self.verify_status = Some("Verifying...".to_string());
+                }
+                cancel_button(ui, self.verify_run.as_ref().map(|(_, cancel)| cancel));
+            });
+            if let Some((rx, _)) = &self.verify_run {
+                match rx.try_recv() {
+                    Ok(result) => {
+                        self.verify_status = Some(match result {
+                            Ok(report) => report.to_string(),
+                            Err(err) => error_text(&err),
+                        });
+                        self.verify_run = None;
+                    }
+                    Err(mpsc::TryRecvError::Empty) => ui.ctx().request_repaint_after(Duration::from_millis(100)),
+                    Err(mpsc::TryRecvError::Disconnected) => self.verify_run = None,
+                }
+            }
+            if let Some(status) = &self.verify_status {
+                egui::ScrollArea::vertical()
+                    .id_salt("verify_report")
+                    .max_height(150.0)
+                    .show(ui, |ui| ui.label(status));
+            }
+        });
+    }
+
+    fn schedule_editor(&mut self, ui: &mut egui::Ui, program_data: &VintageProgramData) {
+        let mut schedules = program_data.folder_settings(&self.folder_bucket).schedules;
+
+        egui::CollapsingHeader::new("Schedules").show(ui, |ui| {
+            let mut removed = None;
+            for (index, schedule) in schedules.iter().enumerate() {
+                ui.horizontal(|ui| {
+                    ui.label(schedule.describe());
+                    if let Some(last_run) = schedule
+                        .last_run
+                        .and_then(|timestamp| chrono::DateTime::from_timestamp(timestamp, 0))
+                    {
+                        ui.label(format!(
+                            "(last run {})",
+                            last_run.with_timezone(&chrono::Local).format("%Y-%m-%d %H:%M")
+                        ));
+                    }
+                    if ui.button("Remove").clicked() {
+                        removed = Some(index);
+                    }
+                });
+            }
+
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("schedule_action")
+                    .selected_text(self.schedule_action.label())
+                    .show_ui(ui, |ui| {
+                        for action in [ScheduledAction::Upload, ScheduledAction::Snapshot] {
+                            ui.selectable_value(&mut self.schedule_action, action, action.label());
+                        }
+                    });
+                ui.radio_value(&mut self.schedule_use_cron, false, "Every N minutes");
+                ui.radio_value(&mut self.schedule_use_cron, true, "Cron");
+                ui.add(egui::TextEdit::singleline(&mut self.schedule_value).desired_width(90.0));
+            });
+            ui.checkbox(&mut self.schedule_while_running, "Only while the game or server runs");
+
+            let mut changed = removed.map(|index| schedules.remove(index)).is_some();
+            if ui.button("Add schedule").clicked() {
+                let rule = if self.schedule_use_cron {
+                    Some(ScheduleRule::Cron {
+                        expression: self.schedule_value.trim().to_string(),
+                    })
+                } else {
+                    self.schedule_value
+                        .trim()
+                        .parse()
+                        .ok()
+                        .map(|minutes| ScheduleRule::Interval { minutes })
+                };
+                match rule {
+                    Some(rule) => {
+                        schedules.push(Schedule {
+                            action: self.schedule_action,
+                            rule,
+                            only_while_game_running: self.schedule_while_running,
+                            last_run: None,
+                        });
+                        changed = true;
+                    }
+                    None => self.schedule_status = Some("Enter the number of minutes".to_string()),
+                }
+            }
+            if changed {
+                self.schedule_status = set_schedules(&self.folder_bucket, schedules)
+                    .err()
+                    .map(|err| err.to_string());
+            }
+            if let Some(status) = &self.schedule_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn handle_watcher_events(&mut self) {
+        while let Ok(event) = self.watcher_receiver.try_recv() {
+            match event {
+                WatcherEvent::GameStarted => {
+                    self.auto_sync_status = Some("Vintage Story started".to_string());
+                }
+                WatcherEvent::GameStopped { upload_in_secs } => {
+                    self.auto_sync_status = Some(format!(
+                        "Vintage Story closed, uploading in {}s",
+                        upload_in_secs
+                    ));
+                }
+                WatcherEvent::WorldsChanged { worlds } => {
+                    self.auto_sync_status = Some(format!("Uploading changed worlds: {}", worlds.join(", ")));
+                }
+                WatcherEvent::ScheduledRun { folder_bucket, action } => {
+                    self.auto_sync_status = Some(format!("Scheduled {} of {}", action.label().to_lowercase(), folder_bucket));
+                }
+                // Shown like an upload started with the button.
+                WatcherEvent::UploadStarted => {
+                    let _ = self.upload_sender.send(UploadEvent::Started);
+                }
+                WatcherEvent::UploadProgress(progress) => {
+                    let _ = self.upload_sender.send(UploadEvent::Progress(progress));
+                }
+                WatcherEvent::UploadFinished => {
+                    let _ = self.upload_sender.send(UploadEvent::Finished);
+                }
+                WatcherEvent::UploadFailed { error } => {
+                    let _ = self.upload_sender.send(UploadEvent::Failed(error));
+                }
+            }
+        }
+    }
+
+    fn sync_settings_editor(&mut self, ui: &mut egui::Ui, program_data: &VintageProgramData) {
+        let settings = program_data.folder_settings(&self.folder_bucket);
+        let editing = (self.folder_bucket.clone(), self.filter_scope);
+        if self.filter_bucket.as_ref() != Some(&editing) {
+            let filter = settings.filter(self.filter_scope);
+            self.filter_include = filter.include.join("\n");
+            self.filter_exclude = filter.exclude.join("\n");
+            self.filter_bucket = Some(editing);
+            self.filter_status = None;
+        }
+
+        egui::CollapsingHeader::new("Sync scopes").show(ui, |ui| {
+            ui.horizontal_wrapped(|ui| {
+                for scope in SyncScope::ALL {
+                    let mut enabled = settings.scopes.contains(&scope);
+                    if ui.checkbox(&mut enabled, scope.label()).changed()
+                        && let Err(err) = set_scope_enabled(&self.folder_bucket, scope, enabled)
+                    {
+                        self.filter_status = Some(err.to_string());
+                    }
+                }
+            });
+        });
+
+        egui::CollapsingHeader::new("Sync rules").show(ui, |ui| {
+            egui::ComboBox::from_label("Scope")
+                .selected_text(self.filter_scope.label())
+                .show_ui(ui, |ui| {
+                    for scope in SyncScope::ALL {
+                        ui.selectable_value(&mut self.filter_scope, scope, scope.label());
+                    }
+                });
+            ui.label("Include (one glob per line):");
+            ui.text_edit_multiline(&mut self.filter_include);
+            ui.label("Exclude (one glob per line):");
+            ui.text_edit_multiline(&mut self.filter_exclude);
+            ui.horizontal(|ui| {
+                if ui.button("Save rules").clicked() {
+                    let filter = SyncFilter {
+                        include: split_rules(&self.filter_include),
+                        exclude: split_rules(&self.filter_exclude),
+                    };
+                    self.filter_status = Some(match set_sync_filter(&self.folder_bucket, self.filter_scope, filter) {
+                        Ok(()) => "Rules saved".to_string(),
+                        Err(err) => err.to_string(),
+                    });
+                }
+                if ui.button("Reset to defaults").clicked() {
+                    let filter = self.filter_scope.default_filter();
+                    self.filter_include = filter.include.join("\n");
+                    self.filter_exclude = filter.exclude.join("\n");
+                }
+            });
+            if let Some(status) = &self.filter_status {
+                ui.label(status);
+            }
+        });
+    }
+
+    fn refresh_game_status(&mut self, ctx: &egui::Context) {
+        if self
+            .game_status_checked
+            .is_some_and(|checked| checked.elapsed() < GAME_STATUS_INTERVAL)
+        {
+            return;
+        }
+        self.game_status = game_status().unwrap_or_default();
+        self.game_status_checked = Some(Instant::now());
+        ctx.request_repaint_after(GAME_STATUS_INTERVAL);
     }
 
     fn handle_events(&mut self) {
         while let Ok(event) = self.upload_receiver.try_recv() {
             match event {
+                UploadEvent::Planned(plan) => {
+                    self.uploading = false;
+                    self.upload_status = format!("Upload plan: {}", plan.summary());
+                    self.upload_progress = 0.0;
+                    self.upload_plan = Some(plan);
+                }
                 UploadEvent::Started => {
                     self.uploading = true;
                     self.upload_status = "Uploading saves...".to_string();
@@ -77,40 +867,143 @@ impl CloudApp {
                 }
                 UploadEvent::Progress(progress) => {
                     self.uploading = true;
-                    let pct = if progress.total_bytes > 0 {
-                        progress.uploaded_bytes as f32 / progress.total_bytes as f32
-                    } else {
-                        0.0
-                    };
-                    self.upload_progress = pct;
+                    self.upload_progress = progress.fraction();
                     if progress.total_bytes > 0 {
-                        let speed_mb_s = if progress.elapsed_secs > 0.0 {
-                            (progress.uploaded_bytes as f32 / 1024.0 / 1024.0) / progress.elapsed_secs
-                        } else {
-                            0.0
-                        };
-                        self.upload_status = format!(
-                            "Uploading {} ({:.1}% @ {:.2} MB/s)",
-                            progress.current_file,
-                            pct * 100.0,
-                            speed_mb_s
-                        );
+                        self.upload_status = progress_status("Uploading", &progress);
                     }
                 }
                 UploadEvent::Finished => {
                     self.uploading = false;
+                    self.upload_cancel = None;
                     self.upload_status = "Upload complete".to_string();
                     self.upload_progress = 1.0;
                 }
                 UploadEvent::Failed(err) => {
                     self.uploading = false;
+                    self.upload_cancel = None;
                     self.upload_status = "Upload failed".to_string();
                     self.upload_error = Some(err);
                     self.upload_progress = 0.0;
                 }
+                UploadEvent::Cancelled => {
+                    self.uploading = false;
+                    self.upload_cancel = None;
+                    self.upload_status = "Upload cancelled; finished files were kept".to_string();
+                    self.upload_progress = 0.0;
+                }
+            }
+        }
+    }
+    /// Plans the upload on a worker thread. A plan that transfers files or
+    /// has conflicts waits for confirmation; otherwise the upload runs right
+    /// away, which only checks worlds back in.
+    fn preview_upload(&mut self) {
+        self.upload_plan = None;
+        let tx = self.upload_sender.clone();
+        let folder = self.folder_bucket.clone();
+        let cancel = self.upload_cancel.insert(CancelToken::new()).clone();
+        thread::spawn(move || match preview_upload(folder.clone()) {
+            Ok(plan) if plan.has_transfers() || plan.has_conflicts() => {
+                let _ = tx.send(UploadEvent::Planned(plan));
+            }
+            Ok(_) => run_upload(folder, tx, Vec::new(), cancel),
+            Err(err) => {
+                let _ = tx.send(UploadEvent::Failed(error_text(&err)));
+            }
+        });
+    }
+
+    /// Runs the upload of the confirmed plan. Only the conflicts the plan
+    /// showed are uploaded over; one that came up since fails the upload.
+    fn start_upload(&mut self) {
+        let accepted_conflicts = self.upload_plan.take().map(|plan| plan.conflict_keys()).unwrap_or_default();
+        let tx = self.upload_sender.clone();
+        let folder = self.folder_bucket.clone();
+        let cancel = self.upload_cancel.insert(CancelToken::new()).clone();
+        thread::spawn(move || run_upload(folder, tx, accepted_conflicts, cancel));
+    }
+
+    /// Runs the download on a worker thread. Unless `confirmed` is set, the
+    /// download first stops at its plan when it transfers files or has
+    /// conflicts or warnings. A confirmed download only replaces the
+    /// conflicts its plan showed.
+    fn start_download(&mut self, confirmed: bool) {
+        let accepted_conflicts = self.download_plan.take().map(|plan| plan.conflict_keys()).unwrap_or_default();
+        let tx = self.download_sender.clone();
+        let folder = self.folder_bucket.clone();
+        let cancel = self.download_cancel.insert(CancelToken::new()).clone();
+        thread::spawn(move || {
+            let _ = tx.send(DownloadEvent::Started);
+            if !confirmed {
+                match preview_download(folder.clone()) {
+                    Ok(plan) if plan.has_transfers() || plan.has_conflicts() || !plan.warnings.is_empty() => {
+                        let _ = tx.send(DownloadEvent::Planned(plan));
+                        return;
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        let _ = tx.send(DownloadEvent::Failed(error_text(&err)));
+                        return;
+                    }
+                }
             }
+            let options = DownloadOptions {
+                accept_warnings: confirmed,
+                accepted_conflicts,
+                cancel,
+            };
+            let progress = |progress| {
+                let _ = tx.send(DownloadEvent::Progress(progress));
+            };
+            match download_save(folder, Some(&progress), options) {
+                Ok(_) => {
+                    let _ = tx.send(DownloadEvent::Finished);
+                }
+                Err(Error::Cancelled) => {
+                    let _ = tx.send(DownloadEvent::Cancelled);
+                }
+                Err(err) => {
+                    let _ = tx.send(DownloadEvent::Failed(error_text(&err)));
+                }
+            }
+        });
+    }
+
+    /// Shows the plans waiting for confirmation with buttons to go ahead or
+    /// cancel.
+    fn plan_confirmation(&mut self, ui: &mut egui::Ui) {
+        if let Some(plan) = &self.upload_plan {
+            show_plan(ui, plan);
+            let label = if plan.has_conflicts() { "Upload anyway" } else { "Upload" };
+            ui.horizontal(|ui| {
+                if ui.button(label).clicked() && !self.uploading {
+                    self.start_upload();
+                }
+                if ui.button("Cancel").clicked() {
+                    self.upload_plan = None;
+                    self.upload_status = "Upload cancelled".to_string();
+                }
+            });
+        }
+        if let Some(plan) = &self.download_plan {
+            show_plan(ui, plan);
+            let label = if plan.warnings.is_empty() && !plan.has_conflicts() {
+                "Download"
+            } else {
+                "Download anyway"
+            };
+            ui.horizontal(|ui| {
+                if ui.button(label).clicked() && !self.downloading {
+                    self.start_download(true);
+                }
+                if ui.button("Cancel").clicked() {
+                    self.download_plan = None;
+                    self.download_status = "Idle".to_string();
+                }
+            });
         }
     }
+
     fn handle_download_events(&mut self) {
         while let Ok(event) = self.download_receiver.try_recv() {
             match event {
@@ -120,26 +1013,173 @@ impl CloudApp {
                     self.download_error = None;
                     self.download_progress = 0.1;
                 }
+                DownloadEvent::Progress(progress) => {
+                    self.downloading = true;
+                    self.download_progress = progress.fraction();
+                    if progress.total_bytes > 0 {
+                        self.download_status = progress_status("Downloading", &progress);
+                    }
+                }
+                DownloadEvent::Planned(plan) => {
+                    self.downloading = false;
+                    self.download_status = format!("Download plan: {}", plan.summary());
+                    self.download_progress = 0.0;
+                    self.download_plan = Some(plan);
+                }
                 DownloadEvent::Finished => {
                     self.downloading = false;
+                    self.download_cancel = None;
                     self.download_status = "Download complete".to_string();
                     self.download_progress = 1.0;
                 }
                 DownloadEvent::Failed(err) => {
                     self.downloading = false;
+                    self.download_cancel = None;
                     self.download_status = "Download failed".to_string();
                     self.download_error = Some(err);
                     self.download_progress = 0.0;
                 }
+                DownloadEvent::Cancelled => {
+                    self.downloading = false;
+                    self.download_cancel = None;
+                    self.download_status = "Download cancelled; finished files were kept".to_string();
+                    self.download_progress = 0.0;
+                }
+            }
+        }
+    }
+}
+
+/// Uploads `folder`, reporting through `tx`. Runs on a worker thread.
+fn run_upload(folder: String, tx: Sender<UploadEvent>, accepted_conflicts: Vec<String>, cancel: CancelToken) {
+    let _ = tx.send(UploadEvent::Started);
+    let progress = |progress| {
+        let _ = tx.send(UploadEvent::Progress(progress));
+    };
+    let result = upload_save(folder, Some(&progress), UploadOptions { accepted_conflicts, cancel });
+    match result {
+        Ok(_) => {
+            let _ = tx.send(UploadEvent::Finished);
+        }
+        Err(Error::Cancelled) => {
+            let _ = tx.send(UploadEvent::Cancelled);
+        }
+        Err(err) => {
+            let _ = tx.send(UploadEvent::Failed(error_text(&err)));
+        }
+    }
+}
+
+/// `config` without application keys, with a profile to edit even when
+/// none is saved yet.
+fn editable_config(config: &AppConfig) -> AppConfig {
+    let mut config = config.redacted();
+    if config.profiles.is_empty() {
+        config.profiles.push(StorageProfile {
+            name: DEFAULT_PROFILE.to_string(),
+            ..StorageProfile::default()
+        });
+    }
+    config
+}
+
+fn encryption_label(encryption: &Encryption) -> &'static str {
+    match encryption {
+        Encryption::None => "Bucket default",
+        Encryption::Aes256 => "AES-256",
+        Encryption::Kms { .. } => "AWS KMS",
+    }
+}
+
+/// A checkbox per profile name; ticking one appends it to `selected`.
+fn profile_checkboxes(ui: &mut egui::Ui, names: &[String], selected: &mut Vec<String>) {
+    for name in names {
+        let mut ticked = selected.contains(name);
+        if ui.checkbox(&mut ticked, name.as_str()).changed() {
+            if ticked {
+                selected.push(name.clone());
+            } else {
+                selected.retain(|profile| profile != name);
             }
         }
     }
 }
 
+/// The error followed by what the user can do about it.
+fn error_text(err: &Error) -> String {
+    match err.hint() {
+        Some(hint) => format!("{}\n{}", err, hint),
+        None => err.to_string(),
+    }
+}
+
+/// E.g. "Downloading world.vcdbs (42.0% @ 3.10 MB/s, 1:05 left)".
+fn progress_status(verb: &str, progress: &TransferProgress) -> String {
+    let eta = progress
+        .eta_secs()
+        .map(|secs| format!(", {}:{:02} left", secs / 60, secs % 60))
+        .unwrap_or_default();
+    format!(
+        "{} {} ({:.1}% @ {:.2} MB/s{})",
+        verb,
+        progress.current_file,
+        progress.fraction() * 100.0,
+        progress.bytes_per_sec() / 1024.0 / 1024.0,
+        eta
+    )
+}
+
+/// Offers to cancel the transfer `cancel` belongs to. Transfers started by
+/// the watchers and schedules have no token and can't be cancelled here.
+fn cancel_button(ui: &mut egui::Ui, cancel: Option<&CancelToken>) {
+    let Some(cancel) = cancel else {
+        return;
+    };
+    if cancel.is_cancelled() {
+        ui.label("Cancelling...");
+    } else if ui.button("Cancel").clicked() {
+        cancel.cancel();
+    }
+}
+
+/// Lists what a plan does: every file not skipped as unchanged, then the
+/// warnings. Conflicts and warnings stand out in yellow.
+fn show_plan(ui: &mut egui::Ui, plan: &SyncPlan) {
+    let heading = match plan.direction {
+        SyncDirection::Upload => "Uploading will:",
+        SyncDirection::Download => "Downloading will:",
+    };
+    ui.label(format!("{} {}", heading, plan.summary()));
+    egui::ScrollArea::vertical()
+        .id_salt(heading)
+        .max_height(150.0)
+        .show(ui, |ui| {
+            for file in plan.files.iter().filter(|file| file.reason != PlanReason::Unchanged) {
+                let line = format!(
+                    "• {:?} {} ({} mb): {}",
+                    file.action,
+                    file.key,
+                    file.size / 1024 / 1024,
+                    file.reason.describe()
+                );
+                if file.action == PlanAction::Conflict {
+                    ui.colored_label(egui::Color32::YELLOW, line);
+                } else {
+                    ui.label(line);
+                }
+            }
+        });
+    for warning in &plan.warnings {
+        ui.colored_label(egui::Color32::YELLOW, format!("• {}", warning));
+    }
+}
+
 impl App for CloudApp {
     fn update(&mut self, ctx: &egui::Context, _: &mut eframe::Frame) {
+        self.handle_watcher_events();
         self.handle_events();
         self.handle_download_events();
+        self.refresh_game_status(ctx);
         egui::CentralPanel::default().show(ctx, |ui| {
             let program_data = get_manifest_info().unwrap_or_default();
             //println!("program_data: {:?}", program_data);
@@ -148,7 +1188,10 @@ impl App for CloudApp {
                 .get(&self.folder_bucket)
                 .map(|m| m.files.clone())
                 .unwrap_or_default();
-            let total_saves = manifest.len();
+            let total_saves = manifest
+                .keys()
+                .filter(|key| SyncScope::split_key(key).0 == SyncScope::Saves)
+                .count();
             let total_size = manifest
                 .values()
                 .map(|file_info| file_info.file_size.unwrap_or(0))
@@ -159,32 +1202,16 @@ impl App for CloudApp {
                 .sum::<u64>();
             let mut sorted_entries: Vec<_> = manifest.iter().collect();
             sorted_entries.sort_by_key(|(key, _)| (*key).clone());
+            let checked_out = program_data
+                .checked_out
+                .get(&self.folder_bucket)
+                .cloned()
+                .unwrap_or_default();
 
             ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
                 ui.heading("Vintage Story Cloud Uploader");
                 if ui.button("Upload now").clicked() && !self.uploading {
-                    let tx = self.upload_sender.clone();
-                    let folder = self.folder_bucket.clone();
-                    thread::spawn(move || {
-                        let (progress_tx, progress_rx) = mpsc::channel();
-                        let progress_forward_tx = tx.clone();
-                        thread::spawn(move || {
-                            while let Ok(progress) = progress_rx.recv() {
-                                let _ = progress_forward_tx.send(UploadEvent::Progress(progress));
-                            }
-                        });
-
-                        let _ = tx.send(UploadEvent::Started);
-                        let result = upload_save(folder, Some(progress_tx));
-                        match result {
-                            Ok(_) => {
-                                let _ = tx.send(UploadEvent::Finished);
-                            }
-                            Err(err) => {
-                                let _ = tx.send(UploadEvent::Failed(err.to_string()));
-                            }
-                        }
-                    });
+                    self.preview_upload();
                 }
             });
 
@@ -197,9 +1224,14 @@ impl App for CloudApp {
                     // Left column: Statistics
                     ui.with_layout(egui::Layout::top_down(egui::Align::LEFT), |ui| {
                         ui.label("Statistics:");
-                        ui.label(&format!("Total saves: {}", total_saves));
-                        ui.label(&format!("Total size: {} mb", total_size / 1024 / 1024));
-                        ui.label(&format!("Total playtime: {}s", total_playtime));
+                        ui.label(format!("Total saves: {}", total_saves));
+                        ui.label(format!("Total size: {} mb", total_size / 1024 / 1024));
+                        ui.label(format!("Total playtime: {}s", total_playtime));
+                        if self.game_status.open_worlds.is_empty() {
+                            ui.label(self.game_status.summary());
+                        } else {
+                            ui.colored_label(egui::Color32::YELLOW, self.game_status.summary());
+                        }
                     });
 
                     // Right column: Controls and manifest
@@ -209,32 +1241,37 @@ impl App for CloudApp {
                             columns[0].set_max_height(0.0);
                             columns[0].with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
                                 ui.heading("Select folder:");
-                                ui.text_edit_singleline(&mut self.folder_bucket);
+                                let mut changed = ui.text_edit_singleline(&mut self.folder_bucket).changed();
+                                ui.menu_button("▾", |ui| {
+                                    for folder_bucket in known_folder_buckets().unwrap_or_default() {
+                                        if ui.button(&folder_bucket).clicked() {
+                                            self.folder_bucket = folder_bucket;
+                                            changed = true;
+                                            ui.close();
+                                        }
+                                    }
+                                });
+                                if changed {
+                                    if let Some(watcher) = &self.game_watcher {
+                                        watcher.set_folder_bucket(&self.folder_bucket);
+                                    }
+                                    if let Some(watcher) = &self.save_watcher {
+                                        watcher.set_folder_bucket(&self.folder_bucket);
+                                    }
+                                }
                             });
                             
                             columns[1].set_max_height(0.0);
                             columns[1].with_layout(egui::Layout::left_to_right(egui::Align::Center), |ui| {
                                 if ui.button("Download now").clicked() && !self.downloading {
-                                    let tx = self.download_sender.clone();
-                                    let folder = self.folder_bucket.clone();
-                                    thread::spawn(move || {
-                                        let _ = tx.send(DownloadEvent::Started);
-                                        let result = download_save(folder);
-                                        match result {
-                                            Ok(_) => {
-                                                let _ = tx.send(DownloadEvent::Finished);
-                                            }
-                                            Err(err) => {
-                                                let _ = tx.send(DownloadEvent::Failed(err.to_string()));
-                                            }
-                                        }
-                                    });
+                                    self.start_download(false);
                                 }
                             });
                         });
                         if self.uploading {
                             ui.add(egui::ProgressBar::new(self.upload_progress).show_percentage());
                             ui.label(&self.upload_status);
+                            cancel_button(ui, self.upload_cancel.as_ref());
                         } else {
                             ui.label(&self.upload_status);
                         }
@@ -246,6 +1283,7 @@ impl App for CloudApp {
                         if self.downloading {
                             ui.add(egui::ProgressBar::new(self.download_progress).show_percentage());
                             ui.label(&self.download_status);
+                            cancel_button(ui, self.download_cancel.as_ref());
                         } else {
                             ui.label(&self.download_status);
                         }
@@ -254,18 +1292,39 @@ impl App for CloudApp {
                             ui.colored_label(egui::Color32::RED, error);
                         }
 
-                        ui.heading(&format!("Cloud Saves in folder {} :{}", self.folder_bucket, total_saves));
+                        self.plan_confirmation(ui);
+
+                        self.storage_editor(ui);
+                        self.profile_editor(ui);
+                        self.device_editor(ui);
+                        self.sync_settings_editor(ui, &program_data);
+                        self.auto_sync_editor(ui);
+                        self.hooks_editor(ui);
+                        self.schedule_editor(ui, &program_data);
+                        self.verify_editor(ui);
+
+                        ui.heading(format!("Cloud Saves in folder {} :{}", self.folder_bucket, total_saves));
 
                         egui::Grid::new("manifest_grid")
-                            .num_columns(4)
+                            .num_columns(6)
                             .spacing([10.0, 4.0])
                             .striped(true)
                             .show(ui, |ui| {
                                 for (key, file_info) in &sorted_entries {
                                     ui.label((*key).as_str());
-                                    ui.label(&format!("{} )", file_info.world_name));
-                                    ui.label(&format!("{} mb", file_info.file_size.unwrap_or(0) / 1024 / 1024));
-                                    ui.label(&format!("{}s", file_info.playtime));
+                                    ui.label(format!("{} )", file_info.world_name));
+                                    ui.label(format!("{} mb", file_info.file_size.unwrap_or(0) / 1024 / 1024));
+                                    ui.label(format!("{}s", file_info.playtime));
+                                    ui.label(file_info.game_version.as_deref().unwrap_or("-"));
+                                    let mut uploaded = file_info
+                                        .last_upload
+                                        .as_ref()
+                                        .map(|upload| upload.describe())
+                                        .unwrap_or_default();
+                                    if checked_out.contains(*key) {
+                                        uploaded.push_str(" (checked out here)");
+                                    }
+                                    ui.label(uploaded);
                                     ui.end_row();
                                 }
                             });
@@ -279,10 +1338,41 @@ impl App for CloudApp {
 }
 
 
+fn split_rules(text: &str) -> Vec<String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .map(str::to_string)
+        .collect()
+}
 
 fn main() -> eframe::Result<()> {
     println!("Starting Cloud Save Uploader");
-    dotenv().ok();
+    if cli::headless_requested() {
+        if let Err(err) = cli::run_headless() {
+            eprintln!("{}", error_text(&err));
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some(options) = cli::verify_requested() {
+        match cli::run_verify(options) {
+            Ok(true) => {}
+            Ok(false) => std::process::exit(2),
+            Err(err) => {
+                eprintln!("{}", error_text(&err));
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    if let Some(directions) = cli::dry_run_requested() {
+        if let Err(err) = cli::run_dry_run(&directions) {
+            eprintln!("{}", error_text(&err));
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
     let viewport = egui::ViewportBuilder::default()
         .with_resizable(false)
         .with_inner_size(egui::vec2(700.0, 500.0));             
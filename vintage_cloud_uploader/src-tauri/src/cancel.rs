@@ -0,0 +1,35 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use vscs_core::cancel::CancelToken;
+
+/// Transfers started from the frontend that are still running, by ID.
+static TRANSFERS: Mutex<BTreeMap<u64, CancelToken>> = Mutex::new(BTreeMap::new());
+static NEXT_TRANSFER_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Registers a new transfer, returning its ID and the token that cancels it.
+pub fn begin_transfer() -> (u64, CancelToken) {
+    let transfer_id = NEXT_TRANSFER_ID.fetch_add(1, Ordering::Relaxed);
+    let cancel = CancelToken::new();
+    if let Ok(mut transfers) = TRANSFERS.lock() {
+        transfers.insert(transfer_id, cancel.clone());
+    }
+    (transfer_id, cancel)
+}
+
+/// Forgets a transfer once it is over.
+pub fn end_transfer(transfer_id: u64) {
+    if let Ok(mut transfers) = TRANSFERS.lock() {
+        transfers.remove(&transfer_id);
+    }
+}
+
+/// Cancels the running transfer `transfer_id`; one that already ended is
+/// left alone.
+#[tauri::command]
+pub fn cancel_transfer(transfer_id: u64) {
+    if let Some(cancel) = TRANSFERS.lock().ok().and_then(|transfers| transfers.get(&transfer_id).cloned()) {
+        cancel.cancel();
+    }
+}
@@ -0,0 +1,42 @@
+use serde::Serialize;
+use vscs_core::config::{add_folder_bucket, known_folder_buckets, load_config, set_config, AppConfig, StorageConfig};
+use vscs_core::upload_core::check_connection;
+use vscs_core::Error;
+
+/// The configuration as the settings screen gets it: without the
+/// application keys, but saying which profiles have one stored.
+#[derive(Serialize)]
+pub struct ConfigView {
+    config: AppConfig,
+    profiles_with_key: Vec<String>,
+}
+
+#[tauri::command]
+pub fn get_config() -> Result<ConfigView, Error> {
+    let config = load_config()?;
+    Ok(ConfigView {
+        profiles_with_key: config.profiles_with_key(),
+        config: config.redacted(),
+    })
+}
+
+#[tauri::command]
+pub fn update_config(config: AppConfig) -> Result<(), Error> {
+    set_config(config)
+}
+
+/// Tries `storage` as edited, before it is saved.
+#[tauri::command]
+pub async fn test_connection(storage: StorageConfig) -> Result<(), Error> {
+    check_connection(storage).await
+}
+
+#[tauri::command]
+pub fn get_folder_buckets() -> Result<Vec<String>, Error> {
+    known_folder_buckets()
+}
+
+#[tauri::command]
+pub fn remember_folder_bucket(folder_bucket: String) -> Result<(), Error> {
+    add_folder_bucket(&folder_bucket)
+}
@@ -0,0 +1,12 @@
+use vscs_core::device::{device_identity, set_device_name, DeviceIdentity};
+use vscs_core::Error;
+
+#[tauri::command]
+pub fn get_device_identity() -> Result<DeviceIdentity, Error> {
+    device_identity()
+}
+
+#[tauri::command]
+pub fn update_device_name(name: String) -> Result<(), Error> {
+    set_device_name(&name)
+}
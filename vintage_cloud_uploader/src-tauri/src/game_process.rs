@@ -0,0 +1,7 @@
+use vscs_core::game_process::{game_status, GameStatus};
+use vscs_core::Error;
+
+#[tauri::command]
+pub fn get_game_status() -> Result<GameStatus, Error> {
+    game_status()
+}
@@ -0,0 +1,94 @@
+use std::sync::mpsc::{self, Sender};
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+
+use vscs_core::game_watcher::{launch_with_sync, GameWatcher, WatcherEvent};
+use vscs_core::manifest_info::{get_manifest_info, set_auto_sync, AutoSyncSettings};
+use vscs_core::save_watcher::SaveWatcher;
+use vscs_core::Error;
+
+/// The watchers of the running app, replaced whenever auto sync changes.
+static WATCHER: Mutex<Option<GameWatcher>> = Mutex::new(None);
+static SAVE_WATCHER: Mutex<Option<SaveWatcher>> = Mutex::new(None);
+
+/// Forwards watcher events to the frontend as `auto-sync` events until the
+/// watcher holding the returned sender stops.
+pub fn forward_events(app: &tauri::AppHandle) -> Sender<WatcherEvent> {
+    use tauri::Emitter;
+
+    let (events_tx, events_rx) = mpsc::channel();
+    let app = app.clone();
+    thread::spawn(move || {
+        while let Ok(event) = events_rx.recv() {
+            let _ = app.emit("auto-sync", event);
+        }
+    });
+    events_tx
+}
+
+/// Starts or stops the game and save watchers to match `settings`.
+pub fn apply_auto_sync(app: &tauri::AppHandle, folder_bucket: String, settings: &AutoSyncSettings) -> Result<(), Error> {
+    let watcher = settings.upload_on_exit.then(|| {
+        GameWatcher::start(
+            folder_bucket.clone(),
+            Duration::from_secs(settings.upload_delay_secs),
+            forward_events(app),
+        )
+    });
+    if let Ok(mut current) = WATCHER.lock() {
+        *current = watcher;
+    }
+
+    let save_watcher = if settings.watch_saves {
+        Some(SaveWatcher::start(
+            folder_bucket,
+            Duration::from_secs(settings.quiet_period_secs),
+            forward_events(app),
+        )?)
+    } else {
+        None
+    };
+    if let Ok(mut current) = SAVE_WATCHER.lock() {
+        *current = save_watcher;
+    }
+    Ok(())
+}
+
+/// Brings back the watcher saved in the program data when the app starts.
+pub fn restore_auto_sync(app: &tauri::AppHandle) {
+    if let Ok(program_data) = get_manifest_info() {
+        if let Err(err) = apply_auto_sync(app, program_data.current_used_bucket, &program_data.auto_sync) {
+            println!("Failed to start auto sync: {}", err);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn update_auto_sync(
+    app: tauri::AppHandle,
+    folder_bucket: String,
+    settings: AutoSyncSettings,
+) -> Result<(), Error> {
+    set_auto_sync(settings.clone())?;
+    apply_auto_sync(&app, folder_bucket, &settings)
+}
+
+#[tauri::command]
+pub fn set_watched_folder(folder_bucket: String) {
+    if let Some(watcher) = WATCHER.lock().ok().as_ref().and_then(|current| current.as_ref()) {
+        watcher.set_folder_bucket(&folder_bucket);
+    }
+    if let Some(watcher) = SAVE_WATCHER.lock().ok().as_ref().and_then(|current| current.as_ref()) {
+        watcher.set_folder_bucket(&folder_bucket);
+    }
+}
+
+#[tauri::command]
+pub async fn launch_game_synced(folder_bucket: String) -> Result<(), Error> {
+    let settings = get_manifest_info()?.auto_sync;
+    // The download runs its own runtime, so keep it off the async workers.
+    tauri::async_runtime::spawn_blocking(move || launch_with_sync(folder_bucket, &settings))
+        .await
+        .map_err(Error::other)?
+}
@@ -0,0 +1,7 @@
+use vscs_core::hooks::HookSettings;
+use vscs_core::Error;
+
+#[tauri::command]
+pub fn update_hooks(hooks: HookSettings) -> Result<(), Error> {
+    vscs_core::manifest_info::set_hooks(hooks)
+}
@@ -1,6 +1,15 @@
-mod helper_functions;
+mod cancel;
+mod config;
+mod device;
+mod game_process;
+mod game_watcher;
+mod hooks;
 mod manifest_info;
+mod profile;
+mod scheduler;
 mod upload_core;
+mod verify;
+mod world_lock;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -12,12 +21,41 @@ fn greet(name: &str) -> String {
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
-    // Load .env file if it exists (ignores errors if file doesn't exist)
-    let _ = dotenv::dotenv();
-    
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
-        .invoke_handler(tauri::generate_handler![greet, manifest_info::get_manifest_info, upload_core::run_upload, upload_core::run_download])
+        .setup(|app| {
+            game_watcher::restore_auto_sync(app.handle());
+            scheduler::start_scheduler(app.handle());
+            world_lock::start_lease_keeper();
+            Ok(())
+        })
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            config::get_config,
+            config::update_config,
+            config::test_connection,
+            config::get_folder_buckets,
+            config::remember_folder_bucket,
+            device::get_device_identity,
+            device::update_device_name,
+            game_process::get_game_status,
+            game_watcher::update_auto_sync,
+            game_watcher::set_watched_folder,
+            game_watcher::launch_game_synced,
+            scheduler::update_schedules,
+            manifest_info::get_manifest_info,
+            manifest_info::get_folder_settings,
+            manifest_info::update_sync_filter,
+            manifest_info::update_scope_enabled,
+            profile::update_profile,
+            hooks::update_hooks,
+            upload_core::run_upload,
+            upload_core::get_upload_plan,
+            upload_core::get_download_plan,
+            upload_core::run_download,
+            verify::verify_backup,
+            cancel::cancel_transfer
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -0,0 +1,10 @@
+use vscs_core::profile::DataProfile;
+use vscs_core::Error;
+
+#[tauri::command]
+pub fn update_profile(app: tauri::AppHandle, profile: DataProfile) -> Result<(), Error> {
+    vscs_core::manifest_info::set_profile(profile)?;
+    // The save watcher follows the Saves folder of the new profile.
+    crate::game_watcher::restore_auto_sync(&app);
+    Ok(())
+}
@@ -0,0 +1,19 @@
+use std::sync::Mutex;
+
+use vscs_core::scheduler::{Schedule, Scheduler};
+use vscs_core::Error;
+
+/// The scheduler of the running app; it lives as long as the app.
+static SCHEDULER: Mutex<Option<Scheduler>> = Mutex::new(None);
+
+pub fn start_scheduler(app: &tauri::AppHandle) {
+    let scheduler = Scheduler::start(crate::game_watcher::forward_events(app));
+    if let Ok(mut current) = SCHEDULER.lock() {
+        *current = Some(scheduler);
+    }
+}
+
+#[tauri::command]
+pub fn update_schedules(folder_bucket: String, schedules: Vec<Schedule>) -> Result<(), Error> {
+    vscs_core::manifest_info::set_schedules(&folder_bucket, schedules)
+}
@@ -0,0 +1,9 @@
+use vscs_core::verify::{run_verify, VerifyOptions, VerifyReport};
+use vscs_core::Error;
+
+/// Checks the backup of `folder_bucket`. Resolves once every object is
+/// checked, which with `download` takes as long as fetching them all.
+#[tauri::command]
+pub async fn verify_backup(folder_bucket: String, options: VerifyOptions) -> Result<VerifyReport, Error> {
+    run_verify(&folder_bucket, options, None).await
+}
@@ -0,0 +1,16 @@
+use std::sync::Mutex;
+
+use vscs_core::manifest_info::{get_manifest_info, LockSettings};
+use vscs_core::world_lock::LeaseKeeper;
+
+/// The lease keeper of the running app; it lives as long as the app.
+static LEASE_KEEPER: Mutex<Option<LeaseKeeper>> = Mutex::new(None);
+
+pub fn start_lease_keeper() {
+    let lease = get_manifest_info()
+        .map(|program_data| program_data.locking.lease())
+        .unwrap_or_else(|_| LockSettings::default().lease());
+    if let Ok(mut current) = LEASE_KEEPER.lock() {
+        *current = Some(LeaseKeeper::start(lease));
+    }
+}
@@ -2,6 +2,16 @@ import { useState } from "react"
 import { UploadProgress } from "@/components/dashboard/UploadProgress"
 import { FolderSelector } from "@/components/dashboard/FolderSelector"
 import { BackupStats } from "@/components/dashboard/BackupStats"
+import { SyncScopes } from "@/components/dashboard/SyncScopes"
+import { GameStatus } from "@/components/dashboard/GameStatus"
+import { AutoSync } from "@/components/dashboard/AutoSync"
+import { Schedules } from "@/components/dashboard/Schedules"
+import { VerifyBackup } from "@/components/dashboard/VerifyBackup"
+import { DataProfileSettings } from "@/components/dashboard/DataProfileSettings"
+import { StorageSettings } from "@/components/dashboard/StorageSettings"
+import { Hooks } from "@/components/dashboard/Hooks"
+import { Worlds } from "@/components/dashboard/Worlds"
+import { DeviceName } from "@/components/dashboard/DeviceName"
 import { ScrollArea } from "@/components/ui/scroll-area"
 import { Cloud, Settings } from "lucide-react"
 import { Button } from "@/components/ui/button"
@@ -42,10 +52,24 @@ export default function App() {
 
               {/* Progress Section */}
               <UploadProgress selectedFolder={selectedFolder} />
+              <Worlds selectedFolder={selectedFolder} />
+
+              {/* What gets synced */}
+              <SyncScopes selectedFolder={selectedFolder} />
+
+              {/* Where and how it is synced */}
+              <StorageSettings />
+              <DataProfileSettings />
+              <AutoSync selectedFolder={selectedFolder} />
+              <Hooks />
+              <Schedules selectedFolder={selectedFolder} />
+              <VerifyBackup selectedFolder={selectedFolder} />
             </div>
 
             {/* Right Column - Stats */}
             <div className="space-y-6">
+              <GameStatus />
+              <DeviceName />
               <BackupStats />
             </div>
           </div>
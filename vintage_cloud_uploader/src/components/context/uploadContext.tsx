@@ -1,57 +1,152 @@
 import { invoke } from "@tauri-apps/api/core"
-import { createContext, useContext, useState, useCallback, ReactNode } from "react"
+import { listen } from "@tauri-apps/api/event"
+import { createContext, useContext, useState, useCallback, useEffect, useRef, ReactNode } from "react"
 import { useManifest } from "./manifestContext"
+import type {
+  SyncDirection,
+  SyncError,
+  SyncErrorEvent,
+  SyncFileDoneEvent,
+  SyncFinishedEvent,
+  SyncPlan,
+  SyncProgressEvent,
+  TransferProgress,
+} from "@/lib/types"
+import { toSyncError } from "@/lib/types"
+
+// An upload or download running in the backend
+export interface Transfer {
+  id: number
+  direction: SyncDirection
+  folderBucket: string
+  progress: TransferProgress | null
+  filesDone: string[]
+}
 
 interface UploadContextType {
   isUploading: boolean
   isDownloading: boolean
-  error: string | null
+  error: SyncError | null
+  // Running transfers, in the order they started
+  transfers: Transfer[]
+  // What the next upload or download would do; it waits for confirmPlan/cancelPlan
+  plan: SyncPlan | null
   upload: (folderName: string) => Promise<void>
   download: (folderName: string) => Promise<void>
+  confirmPlan: () => Promise<void>
+  cancelPlan: () => void
+  // Stops a running transfer; files already transferred are kept
+  cancelTransfer: (id: number) => Promise<void>
 }
 
 const UploadContext = createContext<UploadContextType | null>(null)
 
 export const UploadProvider = ({ children }: { children: ReactNode }) => {
-  const [isUploading, setIsUploading] = useState(false)
-  const [isDownloading, setIsDownloading] = useState(false)
-  const [error, setError] = useState<string | null>(null)
+  const [error, setError] = useState<SyncError | null>(null)
+  const [plan, setPlan] = useState<SyncPlan | null>(null)
+  const [transfers, setTransfers] = useState<Transfer[]>([])
+  // Transfers that ended, possibly before their run command returned
+  const ended = useRef(new Set<number>())
   const { refreshManifest } = useManifest()
 
-  const upload = useCallback(async (folderName: string) => {
-    setIsUploading(true)
-    setError(null)
-    try {
-      await invoke("run_upload", { folderBucket: folderName })
-      // Refresh manifest after successful upload
-      await refreshManifest()
-    } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err)
-      setError(errorMessage)
-      throw err
-    } finally {
-      setIsUploading(false)
+  const addTransfer = useCallback((transfer: Transfer) => {
+    if (ended.current.has(transfer.id)) {
+      return
     }
+    setTransfers((current) =>
+      current.some((existing) => existing.id === transfer.id) ? current : [...current, transfer]
+    )
+  }, [])
+
+  const endTransfer = useCallback((id: number) => {
+    ended.current.add(id)
+    setTransfers((current) => current.filter((transfer) => transfer.id !== id))
+    refreshManifest()
   }, [refreshManifest])
 
-  const download = useCallback(async (folderName: string) => {
-    setIsDownloading(true)
+  useEffect(() => {
+    const listeners = [
+      listen<SyncProgressEvent>("sync://progress", ({ payload }) => {
+        const { transfer_id, direction, folder_bucket, ...progress } = payload
+        addTransfer({ id: transfer_id, direction, folderBucket: folder_bucket, progress: null, filesDone: [] })
+        setTransfers((current) => current.map((transfer) =>
+          transfer.id === transfer_id ? { ...transfer, progress } : transfer
+        ))
+      }),
+      listen<SyncFileDoneEvent>("sync://file-done", ({ payload }) => {
+        setTransfers((current) => current.map((transfer) =>
+          transfer.id === payload.transfer_id
+            ? { ...transfer, filesDone: [...transfer.filesDone, payload.file] }
+            : transfer
+        ))
+      }),
+      listen<SyncFinishedEvent>("sync://finished", ({ payload }) => {
+        endTransfer(payload.transfer_id)
+      }),
+      listen<SyncErrorEvent>("sync://error", ({ payload }) => {
+        if (payload.error.kind !== "cancelled") {
+          setError(payload.error)
+        }
+        endTransfer(payload.transfer_id)
+      }),
+    ]
+    return () => {
+      listeners.forEach((unlisten) => unlisten.then((stop) => stop()))
+    }
+  }, [addTransfer, endTransfer])
+
+  const preview = useCallback(async (command: string, folderName: string) => {
     setError(null)
+    setPlan(null)
     try {
-      await invoke("run_download", { folderBucket: folderName })
-      // Refresh manifest after successful download
-      await refreshManifest()
+      setPlan(await invoke<SyncPlan>(command, { folderBucket: folderName }))
     } catch (err) {
-      const errorMessage = err instanceof Error ? err.message : String(err)
-      setError(errorMessage)
+      setError(toSyncError(err))
       throw err
-    } finally {
-      setIsDownloading(false)
     }
-  }, [refreshManifest])
+  }, [])
+
+  const upload = useCallback((folderName: string) => preview("get_upload_plan", folderName), [preview])
+  const download = useCallback((folderName: string) => preview("get_download_plan", folderName), [preview])
+
+  const confirmPlan = useCallback(async () => {
+    if (!plan) {
+      return
+    }
+    setPlan(null)
+    setError(null)
+    // The plan was shown with its warnings and conflicts, so the transfer accepts them.
+    // A conflict that came up since still stops it.
+    const acceptedConflicts = plan.files.filter((file) => file.action === "conflict").map((file) => file.key)
+    const id = plan.direction === "upload"
+      ? await invoke<number>("run_upload", { folderBucket: plan.folder_bucket, acceptedConflicts })
+      : await invoke<number>("run_download", {
+        folderBucket: plan.folder_bucket,
+        acceptWarnings: true,
+        acceptedConflicts,
+      })
+    addTransfer({ id, direction: plan.direction, folderBucket: plan.folder_bucket, progress: null, filesDone: [] })
+  }, [plan, addTransfer])
+
+  const cancelPlan = useCallback(() => setPlan(null), [])
+
+  const cancelTransfer = useCallback(async (id: number) => {
+    await invoke("cancel_transfer", { transferId: id })
+  }, [])
 
   return (
-    <UploadContext.Provider value={{ isUploading, isDownloading, error, upload, download }}>
+    <UploadContext.Provider value={{
+      isUploading: transfers.some((transfer) => transfer.direction === "upload"),
+      isDownloading: transfers.some((transfer) => transfer.direction === "download"),
+      error,
+      transfers,
+      plan,
+      upload,
+      download,
+      confirmPlan,
+      cancelPlan,
+      cancelTransfer,
+    }}>
       {children}
     </UploadContext.Provider>
   )
@@ -64,4 +159,3 @@ export const useUpload = () => {
   }
   return context
 }
-
@@ -0,0 +1,183 @@
+import { invoke } from "@tauri-apps/api/core"
+import { listen } from "@tauri-apps/api/event"
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import { useManifest } from "@/components/context/manifestContext"
+import { describeError, describeProgress, type AutoSyncSettings, type WatcherEvent } from "@/lib/types"
+import { Play } from "lucide-react"
+import { useEffect, useState } from "react"
+
+interface AutoSyncProps {
+  selectedFolder: string
+}
+
+const DEFAULT_SETTINGS: AutoSyncSettings = {
+  upload_on_exit: false,
+  upload_delay_secs: 30,
+  download_before_launch: false,
+  watch_saves: false,
+  quiet_period_secs: 30,
+}
+
+function describeWatcherEvent(event: WatcherEvent): string | null {
+  switch (event.kind) {
+    case "game_started":
+      return "Vintage Story started"
+    case "game_stopped":
+      return `Vintage Story closed, uploading in ${event.upload_in_secs}s`
+    case "worlds_changed":
+      return `Uploading changed worlds: ${event.worlds.join(", ")}`
+    case "scheduled_run":
+      return `Scheduled ${event.action} of ${event.folder_bucket}`
+    case "upload_started":
+      return "Uploading..."
+    case "upload_progress":
+      return event.total_bytes > 0
+        ? `Uploading ${describeProgress(event)}`
+        : null
+    case "upload_finished":
+      return "Automatic upload complete"
+    case "upload_failed":
+      return `Automatic upload failed: ${event.error}`
+  }
+}
+
+export function AutoSync({ selectedFolder }: AutoSyncProps) {
+  const { manifest, refreshManifest } = useManifest()
+  const [status, setStatus] = useState<string | null>(null)
+  const [launching, setLaunching] = useState(false)
+
+  const settings = manifest?.auto_sync ?? DEFAULT_SETTINGS
+
+  // The watcher uploads whichever folder is selected when the game exits
+  useEffect(() => {
+    invoke("set_watched_folder", { folderBucket: selectedFolder }).catch(() => {})
+  }, [selectedFolder])
+
+  useEffect(() => {
+    const unlisten = listen<WatcherEvent>("auto-sync", ({ payload }) => {
+      const message = describeWatcherEvent(payload)
+      if (message) {
+        setStatus(message)
+      }
+      if (payload.kind === "upload_finished") {
+        refreshManifest()
+      }
+    })
+    return () => {
+      unlisten.then((stop) => stop())
+    }
+  }, [refreshManifest])
+
+  const update = async (changes: Partial<AutoSyncSettings>) => {
+    setStatus(null)
+    try {
+      await invoke("update_auto_sync", {
+        folderBucket: selectedFolder,
+        settings: { ...settings, ...changes },
+      })
+      await refreshManifest()
+    } catch (err) {
+      setStatus(describeError(err))
+    }
+  }
+
+  const handleLaunch = async () => {
+    setLaunching(true)
+    setStatus(settings.download_before_launch ? "Downloading latest saves before launch..." : null)
+    try {
+      await invoke("launch_game_synced", { folderBucket: selectedFolder })
+      setStatus("Vintage Story launched")
+      await refreshManifest()
+    } catch (err) {
+      setStatus(describeError(err))
+    } finally {
+      setLaunching(false)
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">Auto Sync</CardTitle>
+        <CardDescription>
+          Sync around your play sessions instead of pressing the buttons.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        <div className="flex items-center gap-2">
+          <input
+            id="upload-on-exit"
+            type="checkbox"
+            className="h-4 w-4 accent-primary"
+            checked={settings.upload_on_exit}
+            disabled={!selectedFolder}
+            onChange={(event) => update({ upload_on_exit: event.target.checked })}
+          />
+          <Label htmlFor="upload-on-exit">Upload after the game exits</Label>
+        </div>
+        <div className="flex items-center gap-2">
+          <Label htmlFor="upload-delay" className="whitespace-nowrap">Wait (seconds)</Label>
+          <Input
+            id="upload-delay"
+            type="number"
+            min={0}
+            className="w-24"
+            value={settings.upload_delay_secs}
+            disabled={!selectedFolder}
+            onChange={(event) => update({ upload_delay_secs: Math.max(0, Number(event.target.value) || 0) })}
+          />
+        </div>
+        <div className="flex items-center gap-2">
+          <input
+            id="download-before-launch"
+            type="checkbox"
+            className="h-4 w-4 accent-primary"
+            checked={settings.download_before_launch}
+            disabled={!selectedFolder}
+            onChange={(event) => update({ download_before_launch: event.target.checked })}
+          />
+          <Label htmlFor="download-before-launch">Download latest before launch</Label>
+        </div>
+        <div className="flex items-center gap-2">
+          <input
+            id="watch-saves"
+            type="checkbox"
+            className="h-4 w-4 accent-primary"
+            checked={settings.watch_saves}
+            disabled={!selectedFolder}
+            onChange={(event) => update({ watch_saves: event.target.checked })}
+          />
+          <Label htmlFor="watch-saves">Upload changed worlds while playing</Label>
+        </div>
+        <div className="flex items-center gap-2">
+          <Label htmlFor="quiet-period" className="whitespace-nowrap">Quiet period (seconds)</Label>
+          <Input
+            id="quiet-period"
+            type="number"
+            min={5}
+            className="w-24"
+            value={settings.quiet_period_secs}
+            disabled={!selectedFolder}
+            onChange={(event) => update({ quiet_period_secs: Math.max(5, Number(event.target.value) || 0) })}
+          />
+        </div>
+        <Button onClick={handleLaunch} disabled={!selectedFolder || launching} className="gap-2">
+          <Play className="h-4 w-4" />
+          Launch Vintage Story
+        </Button>
+        {status && (
+          <p className="text-xs text-muted-foreground">{status}</p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
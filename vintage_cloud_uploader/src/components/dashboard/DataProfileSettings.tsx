@@ -0,0 +1,112 @@
+import { invoke } from "@tauri-apps/api/core"
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import { useManifest } from "@/components/context/manifestContext"
+import { describeError, type DataProfile, type ServerProfile } from "@/lib/types"
+import { useEffect, useState } from "react"
+
+const DEFAULT_SERVER: ServerProfile = {
+  data_path: "",
+  pre_backup_command: null,
+  pre_backup_wait_secs: 10,
+}
+
+export function DataProfileSettings() {
+  const { manifest, refreshManifest } = useManifest()
+  const [isServer, setIsServer] = useState(false)
+  const [server, setServer] = useState<ServerProfile>(DEFAULT_SERVER)
+  const [status, setStatus] = useState<string | null>(null)
+
+  useEffect(() => {
+    const profile = manifest?.profile
+    setIsServer(profile?.kind === "server")
+    if (profile?.kind === "server") {
+      setServer({
+        data_path: profile.data_path,
+        pre_backup_command: profile.pre_backup_command,
+        pre_backup_wait_secs: profile.pre_backup_wait_secs,
+      })
+    }
+  }, [manifest?.profile])
+
+  const handleSave = async () => {
+    setStatus(null)
+    const command = server.pre_backup_command?.trim()
+    const profile: DataProfile = isServer
+      ? { kind: "server", ...server, pre_backup_command: command ? command : null }
+      : { kind: "client" }
+    try {
+      await invoke("update_profile", { profile })
+      await refreshManifest()
+      setStatus("Profile saved")
+    } catch (err) {
+      setStatus(describeError(err))
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">Data Profile</CardTitle>
+        <CardDescription>
+          Sync the game client's data or a dedicated server's data path.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        <select
+          className="h-9 rounded-md border bg-background px-2 text-sm"
+          value={isServer ? "server" : "client"}
+          onChange={(event) => setIsServer(event.target.value === "server")}
+        >
+          <option value="client">Game client</option>
+          <option value="server">Dedicated server</option>
+        </select>
+        {isServer && (
+          <>
+            <div className="space-y-1">
+              <Label htmlFor="server-data-path">Server data path</Label>
+              <Input
+                id="server-data-path"
+                value={server.data_path}
+                placeholder="/var/vintagestory/data"
+                onChange={(event) => setServer({ ...server, data_path: event.target.value })}
+              />
+            </div>
+            <div className="space-y-1">
+              <Label htmlFor="pre-backup-command">Pre-backup command</Label>
+              <Input
+                id="pre-backup-command"
+                value={server.pre_backup_command ?? ""}
+                placeholder="screen -S vs -X stuff '/autosavenow\n'"
+                onChange={(event) => setServer({ ...server, pre_backup_command: event.target.value })}
+              />
+            </div>
+            <div className="flex items-center gap-2">
+              <Label htmlFor="pre-backup-wait">Wait after the command (seconds)</Label>
+              <Input
+                id="pre-backup-wait"
+                type="number"
+                min={0}
+                className="w-24"
+                value={server.pre_backup_wait_secs}
+                onChange={(event) => setServer({ ...server, pre_backup_wait_secs: Number(event.target.value) })}
+              />
+            </div>
+          </>
+        )}
+        <Button onClick={handleSave}>Save profile</Button>
+        {status && (
+          <p className="text-xs text-muted-foreground">{status}</p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
@@ -0,0 +1,51 @@
+import { invoke } from "@tauri-apps/api/core"
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { describeError, type DeviceIdentity } from "@/lib/types"
+import { useEffect, useState } from "react"
+
+export function DeviceName() {
+  const [name, setName] = useState("")
+  const [status, setStatus] = useState<string | null>(null)
+
+  useEffect(() => {
+    invoke<DeviceIdentity>("get_device_identity")
+      .then((device) => setName(device.name))
+      .catch((err) => setStatus(describeError(err)))
+  }, [])
+
+  const handleRename = async () => {
+    setStatus(null)
+    try {
+      await invoke("update_device_name", { name })
+      setStatus("Renamed")
+    } catch (err) {
+      setStatus(describeError(err))
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">This Device</CardTitle>
+        <CardDescription>Shown to your other devices next to what this one uploads.</CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        <div className="flex gap-2">
+          <Input value={name} onChange={(event) => setName(event.target.value)} />
+          <Button onClick={handleRename}>Rename</Button>
+        </div>
+        {status && (
+          <p className="text-xs text-muted-foreground">{status}</p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
@@ -11,6 +11,8 @@ import { Button } from "@/components/ui/button"
 import { useEffect, useState } from "react"
 import { useManifest } from "@/components/context/manifestContext"
 import { Input } from "../ui/input"
+import { invoke } from "@tauri-apps/api/core"
+import { describeError } from "@/lib/types"
 
 import {
     Dialog,
@@ -33,24 +35,36 @@ export function FolderSelector({ selectedFolder, onFolderChange }: FolderSelecto
   const { manifest } = useManifest()
 
   const [newFolderName, setNewFolderName] = useState<string>("")
+  const [error, setError] = useState<string | null>(null)
 
   const handleNewFolderNameChange = (event: React.ChangeEvent<HTMLInputElement>) => {
     setNewFolderName(event.target.value)
   }
 
-  const handleAddNewFolder = () => {
-      setFolderWorlds([...folderWorlds, newFolderName])
+  const handleAddNewFolder = async () => {
+    const name = newFolderName.trim()
+    try {
+      await invoke("remember_folder_bucket", { folderBucket: name })
+      setFolderWorlds(await invoke<string[]>("get_folder_buckets"))
+      onFolderChange(name)
       setNewFolderName("")
+      setError(null)
+    } catch (err) {
+      setError(describeError(err))
+    }
   }
 
   useEffect(() => {
     if (manifest) {
-      const worlds = Object.keys(manifest.all_file_info)
-      setFolderWorlds(worlds)
-      // Set default selection if none selected and worlds available
-      if (!selectedFolder && worlds.length > 0) {
-        onFolderChange(worlds[0])
-      }
+      invoke<string[]>("get_folder_buckets")
+        .then((worlds) => {
+          setFolderWorlds(worlds)
+          // Set default selection if none selected and worlds available
+          if (!selectedFolder && worlds.length > 0) {
+            onFolderChange(worlds[0])
+          }
+        })
+        .catch((err) => setError(describeError(err)))
     }
   }, [manifest])
 
@@ -98,6 +112,9 @@ export function FolderSelector({ selectedFolder, onFolderChange }: FolderSelecto
       <p className="text-xs text-muted-foreground">
         Choose the Vintage Story save folder you want to back up.
       </p>
+      {error && (
+        <p className="text-xs text-destructive">{error}</p>
+      )}
     </div>
   )
 }
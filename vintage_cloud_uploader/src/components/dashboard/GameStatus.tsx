@@ -0,0 +1,55 @@
+import { invoke } from "@tauri-apps/api/core"
+import {
+  Card,
+  CardContent,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Gamepad2 } from "lucide-react"
+import { useEffect, useState } from "react"
+import type { GameStatus as GameStatusInfo } from "@/lib/types"
+
+// How often the game process state is re-checked
+const POLL_INTERVAL_MS = 5000
+
+export function GameStatus() {
+  const [status, setStatus] = useState<GameStatusInfo | null>(null)
+
+  useEffect(() => {
+    const refresh = () => {
+      invoke<GameStatusInfo>("get_game_status")
+        .then(setStatus)
+        .catch(() => setStatus(null))
+    }
+    refresh()
+    const timer = setInterval(refresh, POLL_INTERVAL_MS)
+    return () => clearInterval(timer)
+  }, [])
+
+  const description = !status?.running
+    ? "Not running"
+    : status.open_worlds.length === 0
+      ? "Running, no world open"
+      : `Playing ${status.open_worlds.join(", ")}`
+
+  return (
+    <Card>
+      <CardHeader>
+        <CardTitle className="text-lg">Vintage Story</CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-2">
+        <div className="flex items-center gap-2">
+          <div className="rounded-md bg-primary/10 p-2 text-primary">
+            <Gamepad2 className="h-4 w-4" />
+          </div>
+          <p className="text-sm font-medium">{description}</p>
+        </div>
+        {status?.open_worlds.length ? (
+          <p className="text-xs text-muted-foreground">
+            Open worlds can't be downloaded over. Uploads use a snapshot.
+          </p>
+        ) : null}
+      </CardContent>
+    </Card>
+  )
+}
@@ -0,0 +1,81 @@
+import { invoke } from "@tauri-apps/api/core"
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import { useManifest } from "@/components/context/manifestContext"
+import { describeError, type HookSettings } from "@/lib/types"
+import { useEffect, useState } from "react"
+
+const HOOKS: { key: keyof HookSettings, label: string }[] = [
+  { key: "pre_upload", label: "Before upload" },
+  { key: "post_upload", label: "After upload" },
+  { key: "pre_download", label: "Before download" },
+  { key: "post_download", label: "After download" },
+]
+
+const NO_HOOKS: HookSettings = {
+  pre_upload: null,
+  post_upload: null,
+  pre_download: null,
+  post_download: null,
+}
+
+export function Hooks() {
+  const { manifest, refreshManifest } = useManifest()
+  const [hooks, setHooks] = useState<HookSettings>(NO_HOOKS)
+  const [status, setStatus] = useState<string | null>(null)
+
+  useEffect(() => {
+    setHooks(manifest?.hooks ?? NO_HOOKS)
+  }, [manifest?.hooks])
+
+  const handleSave = async () => {
+    setStatus(null)
+    const cleaned = { ...NO_HOOKS }
+    for (const { key } of HOOKS) {
+      const command = hooks[key]?.trim()
+      cleaned[key] = command ? command : null
+    }
+    try {
+      await invoke("update_hooks", { hooks: cleaned })
+      await refreshManifest()
+      setStatus("Hooks saved")
+    } catch (err) {
+      setStatus(describeError(err))
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">Hooks</CardTitle>
+        <CardDescription>
+          Shell commands run around syncs. A failing pre hook cancels the sync.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        {HOOKS.map(({ key, label }) => (
+          <div key={key} className="space-y-1">
+            <Label htmlFor={`hook-${key}`}>{label}</Label>
+            <Input
+              id={`hook-${key}`}
+              value={hooks[key] ?? ""}
+              onChange={(event) => setHooks({ ...hooks, [key]: event.target.value })}
+            />
+          </div>
+        ))}
+        <Button onClick={handleSave}>Save hooks</Button>
+        {status && (
+          <p className="text-xs text-muted-foreground">{status}</p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
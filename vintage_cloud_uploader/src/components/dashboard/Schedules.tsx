@@ -0,0 +1,137 @@
+import { invoke } from "@tauri-apps/api/core"
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import { useManifest } from "@/components/context/manifestContext"
+import { describeError, type Schedule, type ScheduledAction } from "@/lib/types"
+import { Trash2 } from "lucide-react"
+import { useState } from "react"
+
+interface SchedulesProps {
+  selectedFolder: string
+}
+
+function describeSchedule(schedule: Schedule): string {
+  const action = schedule.action === "upload" ? "Upload" : "Snapshot"
+  const when = schedule.rule.kind === "interval"
+    ? `every ${schedule.rule.minutes} min`
+    : `cron '${schedule.rule.expression}'`
+  return `${action} ${when}${schedule.only_while_game_running ? " while the game runs" : ""}`
+}
+
+export function Schedules({ selectedFolder }: SchedulesProps) {
+  const { manifest, refreshManifest } = useManifest()
+  const [action, setAction] = useState<ScheduledAction>("upload")
+  const [useCron, setUseCron] = useState(false)
+  const [value, setValue] = useState("30")
+  const [whileRunning, setWhileRunning] = useState(false)
+  const [error, setError] = useState<string | null>(null)
+
+  const schedules: Schedule[] = manifest?.folder_settings?.[selectedFolder]?.schedules ?? []
+
+  const save = async (next: Schedule[]) => {
+    setError(null)
+    try {
+      await invoke("update_schedules", { folderBucket: selectedFolder, schedules: next })
+      await refreshManifest()
+    } catch (err) {
+      setError(describeError(err))
+    }
+  }
+
+  const handleAdd = () => {
+    const minutes = Number(value)
+    if (!useCron && !(minutes > 0)) {
+      setError("Enter the number of minutes")
+      return
+    }
+    save([
+      ...schedules,
+      {
+        action,
+        rule: useCron ? { kind: "cron", expression: value.trim() } : { kind: "interval", minutes },
+        only_while_game_running: whileRunning,
+        last_run: null,
+      },
+    ])
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">Schedules</CardTitle>
+        <CardDescription>
+          Upload or snapshot this folder on a timer. Missed runs catch up once.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        {schedules.map((schedule, index) => (
+          <div key={index} className="flex items-center justify-between gap-2">
+            <div>
+              <p className="text-sm">{describeSchedule(schedule)}</p>
+              {schedule.last_run && (
+                <p className="text-xs text-muted-foreground">
+                  Last run {new Date(schedule.last_run * 1000).toLocaleString()}
+                </p>
+              )}
+            </div>
+            <Button
+              variant="ghost"
+              size="icon"
+              onClick={() => save(schedules.filter((_, i) => i !== index))}
+            >
+              <Trash2 className="h-4 w-4" />
+            </Button>
+          </div>
+        ))}
+        <div className="flex flex-wrap items-center gap-2">
+          <select
+            className="h-9 rounded-md border bg-background px-2 text-sm"
+            value={action}
+            onChange={(event) => setAction(event.target.value as ScheduledAction)}
+          >
+            <option value="upload">Upload</option>
+            <option value="snapshot">Snapshot</option>
+          </select>
+          <select
+            className="h-9 rounded-md border bg-background px-2 text-sm"
+            value={useCron ? "cron" : "interval"}
+            onChange={(event) => setUseCron(event.target.value === "cron")}
+          >
+            <option value="interval">Every N minutes</option>
+            <option value="cron">Cron</option>
+          </select>
+          <Input
+            className="w-32"
+            value={value}
+            placeholder={useCron ? "0 3 * * *" : "30"}
+            onChange={(event) => setValue(event.target.value)}
+          />
+        </div>
+        <div className="flex items-center gap-2">
+          <input
+            id="schedule-while-running"
+            type="checkbox"
+            className="h-4 w-4 accent-primary"
+            checked={whileRunning}
+            onChange={(event) => setWhileRunning(event.target.checked)}
+          />
+          <Label htmlFor="schedule-while-running">Only while the game or server runs</Label>
+        </div>
+        <Button onClick={handleAdd} disabled={!selectedFolder}>
+          Add schedule
+        </Button>
+        {error && (
+          <p className="text-xs text-destructive">{error}</p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
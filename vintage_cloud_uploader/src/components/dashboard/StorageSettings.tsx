@@ -0,0 +1,428 @@
+import { invoke } from "@tauri-apps/api/core"
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import {
+  describeError,
+  type AppConfig,
+  type Binding,
+  type ConfigView,
+  type Encryption,
+  type StorageConfig,
+  type StorageProfile,
+  type StorageProvider,
+} from "@/lib/types"
+import { useEffect, useState } from "react"
+
+const PROVIDERS: { value: StorageProvider, label: string }[] = [
+  { value: "b2", label: "Backblaze B2" },
+  { value: "s3_compatible", label: "S3-compatible" },
+]
+
+const ENCRYPTIONS: { value: Encryption["kind"], label: string }[] = [
+  { value: "none", label: "Bucket default" },
+  { value: "aes256", label: "AES-256" },
+  { value: "kms", label: "AWS KMS" },
+]
+
+const EMPTY_PROFILE: StorageProfile = {
+  name: "",
+  provider: "b2",
+  bucket: "",
+  region: "",
+  endpoint: "",
+  key_id: "",
+  application_key: "",
+  plaintext_key_fallback: false,
+  prefix: "",
+  encryption: { kind: "none" },
+}
+
+const EMPTY_BINDING: Binding = { profiles: [], worlds: {} }
+
+// Ticking a profile appends it, so the first ticked is the one read from.
+function toggleProfile(profiles: string[], name: string, ticked: boolean) {
+  return ticked ? [...profiles, name] : profiles.filter((profile) => profile !== name)
+}
+
+function ProfileCheckboxes({ names, selected, onChange }: {
+  names: string[],
+  selected: string[],
+  onChange: (profiles: string[]) => void,
+}) {
+  return (
+    <div className="flex flex-wrap gap-2">
+      {names.map((name) => (
+        <label key={name} className="flex items-center gap-1 text-sm">
+          <input
+            type="checkbox"
+            checked={selected.includes(name)}
+            onChange={(event) => onChange(toggleProfile(selected, name, event.target.checked))}
+          />
+          {name}
+        </label>
+      ))}
+    </div>
+  )
+}
+
+const STORAGE_FIELDS: { key: keyof Omit<StorageConfig, "provider" | "plaintext_key_fallback">, label: string, placeholder?: string }[] = [
+  { key: "bucket", label: "Bucket" },
+  { key: "region", label: "Region", placeholder: "Provider default" },
+  { key: "endpoint", label: "Endpoint", placeholder: "B2 endpoint for the region, or AWS" },
+  { key: "key_id", label: "Key ID" },
+]
+
+export function StorageSettings() {
+  const [config, setConfig] = useState<AppConfig | null>(null)
+  const [profilesWithKey, setProfilesWithKey] = useState<string[]>([])
+  const [selected, setSelected] = useState(0)
+  const [newFolder, setNewFolder] = useState("")
+  const [newWorld, setNewWorld] = useState({ folder: "", world: "" })
+  const [status, setStatus] = useState<string | null>(null)
+  const [testing, setTesting] = useState(false)
+
+  const loadConfig = async () => {
+    try {
+      const view = await invoke<ConfigView>("get_config")
+      const profiles = view.config.profiles.length > 0
+        ? view.config.profiles
+        : [{ ...EMPTY_PROFILE, name: "default" }]
+      setConfig({ ...view.config, profiles })
+      setProfilesWithKey(view.profiles_with_key)
+    } catch (err) {
+      setStatus(describeError(err))
+    }
+  }
+
+  useEffect(() => {
+    loadConfig()
+  }, [])
+
+  if (!config) {
+    return null
+  }
+
+  const index = Math.min(selected, config.profiles.length - 1)
+  const profile = config.profiles[index]
+  const names = config.profiles.map(({ name }) => name)
+
+  const setProfile = (changes: Partial<StorageProfile>) =>
+    setConfig({
+      ...config,
+      profiles: config.profiles.map((existing, i) => (i === index ? { ...existing, ...changes } : existing)),
+    })
+
+  const setBinding = (folder: string, changes: Partial<Binding>) =>
+    setConfig({
+      ...config,
+      bindings: {
+        ...config.bindings,
+        [folder]: { ...EMPTY_BINDING, ...config.bindings[folder], ...changes },
+      },
+    })
+
+  const handleEncryption = (kind: Encryption["kind"]) =>
+    setProfile({ encryption: kind === "kms" ? { kind, key_id: "" } : { kind } })
+
+  const handleAddProfile = () => {
+    setConfig({
+      ...config,
+      profiles: [...config.profiles, { ...EMPTY_PROFILE, name: `profile-${config.profiles.length + 1}` }],
+    })
+    setSelected(config.profiles.length)
+  }
+
+  const handleRemoveProfile = () => {
+    setConfig({ ...config, profiles: config.profiles.filter((_, i) => i !== index) })
+    setSelected(0)
+  }
+
+  const handleAddWorld = () => {
+    const folder = newWorld.folder.trim()
+    const world = newWorld.world.trim()
+    if (folder && world) {
+      const worlds = config.bindings[folder]?.worlds ?? {}
+      setBinding(folder, { worlds: { ...worlds, [world]: worlds[world] ?? [names[0]] } })
+    }
+    setNewWorld({ folder: "", world: "" })
+  }
+
+  const handleRemoveWorld = (folder: string, world: string) => {
+    const { [world]: _, ...worlds } = config.bindings[folder]?.worlds ?? {}
+    setBinding(folder, { worlds })
+  }
+
+  const handleAddFolder = () => {
+    const name = newFolder.trim()
+    if (name && !config.folder_buckets.includes(name)) {
+      setConfig({ ...config, folder_buckets: [...config.folder_buckets, name] })
+    }
+    setNewFolder("")
+  }
+
+  const handleRemoveFolder = (folder: string) => {
+    const { [folder]: _, ...prefixes } = config.prefixes
+    const { [folder]: __, ...bindings } = config.bindings
+    setConfig({
+      ...config,
+      folder_buckets: config.folder_buckets.filter((known) => known !== folder),
+      prefixes,
+      bindings,
+    })
+  }
+
+  const handleTest = async () => {
+    setStatus(null)
+    setTesting(true)
+    try {
+      const { provider, bucket, region, endpoint, key_id, application_key, plaintext_key_fallback } = profile
+      const storage: StorageConfig = {
+        provider, bucket, region, endpoint, key_id, application_key, plaintext_key_fallback,
+      }
+      await invoke("test_connection", { storage })
+      setStatus("Connected to the bucket")
+    } catch (err) {
+      setStatus(describeError(err))
+    } finally {
+      setTesting(false)
+    }
+  }
+
+  const handleSave = async () => {
+    setStatus(null)
+    try {
+      await invoke("update_config", { config })
+      await loadConfig()
+      setStatus("Storage settings saved")
+    } catch (err) {
+      setStatus(describeError(err))
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">Storage</CardTitle>
+        <CardDescription>
+          Where the saves are uploaded. Changes apply to the next transfer.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        <div className="flex flex-wrap items-center gap-2">
+          <select
+            className="h-9 rounded-md border bg-background px-2 text-sm"
+            value={index}
+            onChange={(event) => setSelected(Number(event.target.value))}
+          >
+            {config.profiles.map(({ name }, i) => (
+              <option key={i} value={i}>{name || "(unnamed)"}</option>
+            ))}
+          </select>
+          <Button variant="outline" onClick={handleAddProfile}>Add profile</Button>
+          {config.profiles.length > 1 && (
+            <Button variant="outline" onClick={handleRemoveProfile}>Remove profile</Button>
+          )}
+        </div>
+        <p className="text-xs text-muted-foreground">
+          Folders without a binding are stored in the first profile.
+        </p>
+        <div className="space-y-1">
+          <Label htmlFor="storage-name">Profile name</Label>
+          <Input
+            id="storage-name"
+            value={profile.name}
+            onChange={(event) => setProfile({ name: event.target.value })}
+          />
+        </div>
+        <select
+          className="h-9 rounded-md border bg-background px-2 text-sm"
+          value={profile.provider}
+          onChange={(event) => setProfile({ provider: event.target.value as StorageProvider })}
+        >
+          {PROVIDERS.map(({ value, label }) => (
+            <option key={value} value={value}>{label}</option>
+          ))}
+        </select>
+        {STORAGE_FIELDS.map(({ key, label, placeholder }) => (
+          <div key={key} className="space-y-1">
+            <Label htmlFor={`storage-${key}`}>{label}</Label>
+            <Input
+              id={`storage-${key}`}
+              value={profile[key]}
+              placeholder={placeholder}
+              onChange={(event) => setProfile({ [key]: event.target.value })}
+            />
+          </div>
+        ))}
+        <div className="space-y-1">
+          <Label htmlFor="storage-application-key">Application key</Label>
+          <Input
+            id="storage-application-key"
+            type="password"
+            value={profile.application_key}
+            placeholder={profilesWithKey.includes(profile.name) ? "Stored; leave empty to keep it" : undefined}
+            onChange={(event) => setProfile({ application_key: event.target.value })}
+          />
+        </div>
+        <div className="space-y-1">
+          <Label htmlFor="storage-prefix">Prefix in the bucket</Label>
+          <Input
+            id="storage-prefix"
+            value={profile.prefix}
+            placeholder="None"
+            onChange={(event) => setProfile({ prefix: event.target.value })}
+          />
+        </div>
+        <div className="flex gap-2">
+          <select
+            className="h-9 rounded-md border bg-background px-2 text-sm"
+            value={profile.encryption.kind}
+            onChange={(event) => handleEncryption(event.target.value as Encryption["kind"])}
+          >
+            {ENCRYPTIONS.map(({ value, label }) => (
+              <option key={value} value={value}>{label}</option>
+            ))}
+          </select>
+          {profile.encryption.kind === "kms" && (
+            <Input
+              value={profile.encryption.key_id}
+              placeholder="KMS key ID or ARN"
+              onChange={(event) => setProfile({ encryption: { kind: "kms", key_id: event.target.value } })}
+            />
+          )}
+        </div>
+        <label className="flex items-center gap-2 text-sm">
+          <input
+            type="checkbox"
+            checked={profile.plaintext_key_fallback}
+            onChange={(event) => setProfile({ plaintext_key_fallback: event.target.checked })}
+          />
+          Keep the key in the config file if the system keyring is unavailable
+        </label>
+
+        <div className="space-y-2">
+          <Label>Folder buckets, their prefix and the profiles they are stored in</Label>
+          {config.folder_buckets.map((folder) => (
+            <div key={folder} className="space-y-1">
+              <div className="flex items-center gap-2">
+                <span className="w-32 truncate text-sm">{folder}</span>
+                <Input
+                  value={config.prefixes[folder] ?? ""}
+                  placeholder={folder}
+                  onChange={(event) =>
+                    setConfig({ ...config, prefixes: { ...config.prefixes, [folder]: event.target.value } })
+                  }
+                />
+                <Button variant="outline" onClick={() => handleRemoveFolder(folder)}>Remove</Button>
+              </div>
+              <ProfileCheckboxes
+                names={names}
+                selected={config.bindings[folder]?.profiles ?? []}
+                onChange={(profiles) => setBinding(folder, { profiles })}
+              />
+            </div>
+          ))}
+          <div className="flex gap-2">
+            <Input
+              value={newFolder}
+              placeholder="Folder name"
+              onChange={(event) => setNewFolder(event.target.value)}
+            />
+            <Button variant="outline" onClick={handleAddFolder}>Add</Button>
+          </div>
+        </div>
+
+        <div className="space-y-2">
+          <Label>Worlds stored apart from the rest of their folder</Label>
+          {Object.entries(config.bindings).flatMap(([folder, binding]) =>
+            Object.entries(binding.worlds).map(([world, profiles]) => (
+              <div key={`${folder}/${world}`} className="space-y-1">
+                <div className="flex items-center gap-2">
+                  <span className="flex-1 truncate text-sm">{folder}: {world}</span>
+                  <Button variant="outline" onClick={() => handleRemoveWorld(folder, world)}>Remove</Button>
+                </div>
+                <ProfileCheckboxes
+                  names={names}
+                  selected={profiles}
+                  onChange={(chosen) =>
+                    setBinding(folder, { worlds: { ...binding.worlds, [world]: chosen } })
+                  }
+                />
+              </div>
+            ))
+          )}
+          <div className="flex gap-2">
+            <Input
+              value={newWorld.folder}
+              placeholder="Folder name"
+              onChange={(event) => setNewWorld({ ...newWorld, folder: event.target.value })}
+            />
+            <Input
+              value={newWorld.world}
+              placeholder="world.vcdbs"
+              onChange={(event) => setNewWorld({ ...newWorld, world: event.target.value })}
+            />
+            <Button variant="outline" onClick={handleAddWorld}>Add world</Button>
+          </div>
+        </div>
+
+        <div className="grid grid-cols-3 gap-2">
+          <div className="space-y-1">
+            <Label htmlFor="retry-attempts">Attempts per request</Label>
+            <Input
+              id="retry-attempts"
+              type="number"
+              min={1}
+              value={config.retry.max_attempts}
+              onChange={(event) =>
+                setConfig({ ...config, retry: { ...config.retry, max_attempts: Number(event.target.value) } })
+              }
+            />
+          </div>
+          <div className="space-y-1">
+            <Label htmlFor="retry-initial">First retry after (ms)</Label>
+            <Input
+              id="retry-initial"
+              type="number"
+              min={0}
+              value={config.retry.initial_delay_ms}
+              onChange={(event) =>
+                setConfig({ ...config, retry: { ...config.retry, initial_delay_ms: Number(event.target.value) } })
+              }
+            />
+          </div>
+          <div className="space-y-1">
+            <Label htmlFor="retry-max">Longest wait (s)</Label>
+            <Input
+              id="retry-max"
+              type="number"
+              min={1}
+              value={config.retry.max_delay_secs}
+              onChange={(event) =>
+                setConfig({ ...config, retry: { ...config.retry, max_delay_secs: Number(event.target.value) } })
+              }
+            />
+          </div>
+        </div>
+
+        <div className="flex gap-2">
+          <Button variant="outline" disabled={testing} onClick={handleTest}>
+            {testing ? "Testing..." : "Test connection"}
+          </Button>
+          <Button onClick={handleSave}>Save storage settings</Button>
+        </div>
+        {status && (
+          <p className="text-xs text-muted-foreground">{status}</p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
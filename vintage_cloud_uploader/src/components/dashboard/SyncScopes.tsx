@@ -0,0 +1,64 @@
+import { invoke } from "@tauri-apps/api/core"
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Label } from "@/components/ui/label"
+import { useManifest } from "@/components/context/manifestContext"
+import { describeError, SYNC_SCOPES, type SyncScope } from "@/lib/types"
+import { useState } from "react"
+
+interface SyncScopesProps {
+  selectedFolder: string
+}
+
+export function SyncScopes({ selectedFolder }: SyncScopesProps) {
+  const { manifest, refreshManifest } = useManifest()
+  const [error, setError] = useState<string | null>(null)
+
+  // Buckets without saved settings only sync their saves
+  const enabledScopes: SyncScope[] =
+    manifest?.folder_settings?.[selectedFolder]?.scopes ?? ["saves"]
+
+  const handleToggle = async (scope: SyncScope, enabled: boolean) => {
+    setError(null)
+    try {
+      await invoke("update_scope_enabled", { folderBucket: selectedFolder, scope, enabled })
+      await refreshManifest()
+    } catch (err) {
+      setError(describeError(err))
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">Sync Scopes</CardTitle>
+        <CardDescription>
+          Choose which parts of VintagestoryData follow this folder.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="grid grid-cols-2 gap-3">
+        {SYNC_SCOPES.map(({ scope, label }) => (
+          <div key={scope} className="flex items-center gap-2">
+            <input
+              id={`scope-${scope}`}
+              type="checkbox"
+              className="h-4 w-4 accent-primary"
+              checked={enabledScopes.includes(scope)}
+              disabled={!selectedFolder}
+              onChange={(event) => handleToggle(scope, event.target.checked)}
+            />
+            <Label htmlFor={`scope-${scope}`}>{label}</Label>
+          </div>
+        ))}
+        {error && (
+          <p className="col-span-2 text-xs text-destructive">{error}</p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
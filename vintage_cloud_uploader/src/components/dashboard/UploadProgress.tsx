@@ -1,27 +1,44 @@
 import {
   Card,
   CardContent,
-  CardDescription,
   CardHeader,
   CardTitle,
 } from "@/components/ui/card"
 import { Progress } from "@/components/ui/progress"
 import { Badge } from "@/components/ui/badge"
-import { UploadCloud, DownloadCloud, AlertCircle } from "lucide-react"
+import { UploadCloud, DownloadCloud, AlertCircle, AlertTriangle } from "lucide-react"
 import { Button } from "@/components/ui/button"
 import {
   ButtonGroup,
   ButtonGroupSeparator,
 } from "@/components/ui/button-group"
-import { useUpload } from "@/components/context/uploadContext"
-import { Alert, AlertDescription } from "@/components/ui/alert"
+import { useUpload, type Transfer } from "@/components/context/uploadContext"
+import { Alert, AlertDescription, AlertTitle } from "@/components/ui/alert"
+import {
+  describeDownloadWarning,
+  describePlan,
+  describeProgress,
+  PLAN_REASONS,
+  type SyncDirection,
+} from "@/lib/types"
 
 interface UploadProgressProps {
   selectedFolder: string
 }
 
 export function UploadProgress({ selectedFolder }: UploadProgressProps) {
-  const { isUploading, isDownloading, error, upload, download } = useUpload()
+  const {
+    isUploading,
+    isDownloading,
+    error,
+    transfers,
+    plan,
+    upload,
+    download,
+    confirmPlan,
+    cancelPlan,
+    cancelTransfer,
+  } = useUpload()
 
   const handleUpload = async () => {
     if (!selectedFolder) {
@@ -47,84 +64,167 @@ export function UploadProgress({ selectedFolder }: UploadProgressProps) {
     }
   }
 
-  const isProcessing = isUploading || isDownloading
+  const handleCancelTransfer = async (id: number) => {
+    try {
+      await cancelTransfer(id)
+    } catch (err) {
+      console.error("Cancel failed:", err)
+    }
+  }
+
+  const handleConfirmPlan = async () => {
+    try {
+      await confirmPlan()
+    } catch (err) {
+      console.error("Sync failed:", err)
+    }
+  }
+
+  // A folder already being uploaded or downloaded can't start the same again
+  const busy = (direction: SyncDirection) =>
+    transfers.some((transfer) => transfer.direction === direction && transfer.folderBucket === selectedFolder)
+
+  // Going ahead replaces the other side's copy of a conflict or ignores a warning
+  const needsConfirmation = plan !== null
+    && (plan.warnings.length > 0 || plan.files.some((file) => file.action === "conflict"))
 
   return (
     <Card>
-      {isProcessing ? (
-        <>
-          <CardHeader className="pb-4">
-            <div className="flex items-center justify-between">
-              <CardTitle className="text-lg">
-                {isUploading ? "Upload Status" : "Download Status"}
-              </CardTitle>
-              <Badge variant="secondary" className="gap-1">
-                {isUploading ? (
-                  <UploadCloud className="h-3 w-3" />
-                ) : (
-                  <DownloadCloud className="h-3 w-3" />
-                )}
-                In Progress
-              </Badge>
-            </div>
-            <CardDescription>
-              {isUploading
-                ? `Backing up "${selectedFolder}" to Cloud`
-                : `Downloading "${selectedFolder}" from Cloud`}
-            </CardDescription>
-          </CardHeader>
-          <CardContent className="space-y-4">
-            <div className="space-y-2">
-              <div className="flex items-center justify-between text-sm">
-                <span className="text-muted-foreground">
-                  {isUploading ? "Uploading files..." : "Downloading files..."}
-                </span>
-                <span className="font-medium">Processing...</span>
-              </div>
-              <Progress value={undefined} className="h-2" />
-            </div>
-          </CardContent>
-        </>
-      ) : (
-        <>
-          <CardHeader className="pb-4">
-            <div className="flex items-center justify-between">
-              <CardTitle className="text-lg">Cloud Save</CardTitle>
-            </div>
-          </CardHeader>
-          <CardContent className="space-y-4">
-            {error && (
-              <Alert variant="destructive">
-                <AlertCircle className="h-4 w-4" />
-                <AlertDescription>{error}</AlertDescription>
-              </Alert>
-            )}
-            <ButtonGroup>
-              <Button
-                onClick={handleUpload}
-                disabled={!selectedFolder || isProcessing}
-              >
-                <UploadCloud className="h-4 w-4" />
-                Upload
-              </Button>
-              <ButtonGroupSeparator />
-              <Button
-                onClick={handleDownload}
-                disabled={!selectedFolder || isProcessing}
-              >
-                <DownloadCloud className="h-4 w-4" />
-                Download
-              </Button>
-            </ButtonGroup>
-            {!selectedFolder && (
-              <p className="text-xs text-muted-foreground">
-                Please select a folder first
-              </p>
+      <CardHeader className="pb-4">
+        <div className="flex items-center justify-between">
+          <CardTitle className="text-lg">Cloud Save</CardTitle>
+          {(isUploading || isDownloading) && (
+            <Badge variant="secondary" className="gap-1">
+              {isUploading ? (
+                <UploadCloud className="h-3 w-3" />
+              ) : (
+                <DownloadCloud className="h-3 w-3" />
+              )}
+              {transfers.length} In Progress
+            </Badge>
+          )}
+        </div>
+      </CardHeader>
+      <CardContent className="space-y-4">
+        {transfers.map((transfer) => (
+          <TransferRow key={transfer.id} transfer={transfer} onCancel={handleCancelTransfer} />
+        ))}
+        {error && (
+          <Alert variant="destructive">
+            <AlertCircle className="h-4 w-4" />
+            <AlertTitle className="whitespace-pre-line">{error.message}</AlertTitle>
+            {error.hint && <AlertDescription>{error.hint}</AlertDescription>}
+          </Alert>
+        )}
+        {plan && (
+          <Alert>
+            {needsConfirmation ? (
+              <AlertTriangle className="h-4 w-4" />
+            ) : plan.direction === "upload" ? (
+              <UploadCloud className="h-4 w-4" />
+            ) : (
+              <DownloadCloud className="h-4 w-4" />
             )}
-          </CardContent>
-        </>
-      )}
+            <AlertTitle>
+              {plan.direction === "upload" ? "Uploading will:" : "Downloading will:"} {describePlan(plan)}
+            </AlertTitle>
+            <AlertDescription>
+              <ul className="max-h-40 list-disc overflow-y-auto pl-4">
+                {plan.files
+                  .filter((file) => file.reason !== "unchanged")
+                  .map((file) => (
+                    <li
+                      key={file.key}
+                      className={file.action === "conflict" ? "text-yellow-600" : undefined}
+                    >
+                      {file.action} {file.key} ({(file.size / 1024 / 1024).toFixed(1)} MB): {PLAN_REASONS[file.reason]}
+                    </li>
+                  ))}
+                {plan.warnings.map((warning, index) => (
+                  <li key={`warning-${index}`} className="text-yellow-600">
+                    {describeDownloadWarning(warning)}
+                  </li>
+                ))}
+              </ul>
+              <div className="mt-3 flex gap-2">
+                <Button
+                  size="sm"
+                  variant={needsConfirmation ? "destructive" : "default"}
+                  onClick={handleConfirmPlan}
+                >
+                  {plan.direction === "upload"
+                    ? needsConfirmation ? "Upload anyway" : "Upload"
+                    : needsConfirmation ? "Download anyway" : "Download"}
+                </Button>
+                <Button size="sm" variant="outline" onClick={cancelPlan}>
+                  Cancel
+                </Button>
+              </div>
+            </AlertDescription>
+          </Alert>
+        )}
+        <ButtonGroup>
+          <Button
+            onClick={handleUpload}
+            disabled={!selectedFolder || busy("upload")}
+          >
+            <UploadCloud className="h-4 w-4" />
+            Upload
+          </Button>
+          <ButtonGroupSeparator />
+          <Button
+            onClick={handleDownload}
+            disabled={!selectedFolder || busy("download")}
+          >
+            <DownloadCloud className="h-4 w-4" />
+            Download
+          </Button>
+        </ButtonGroup>
+        {!selectedFolder && (
+          <p className="text-xs text-muted-foreground">
+            Please select a folder first
+          </p>
+        )}
+      </CardContent>
     </Card>
   )
 }
 
+interface TransferRowProps {
+  transfer: Transfer
+  onCancel: (id: number) => void
+}
+
+// Live progress of one running upload or download
+function TransferRow({ transfer, onCancel }: TransferRowProps) {
+  const { progress } = transfer
+  const verb = transfer.direction === "upload" ? "Uploading" : "Downloading"
+  const percent = progress && progress.total_bytes > 0
+    ? (progress.transferred_bytes / progress.total_bytes) * 100
+    : undefined
+  return (
+    <div className="space-y-2">
+      <div className="flex items-center justify-between text-sm">
+        <span className="text-muted-foreground">
+          {progress && progress.current_file
+            ? `${verb} ${describeProgress(progress)}`
+            : `${verb} "${transfer.folderBucket}"...`}
+        </span>
+        <span className="font-medium">
+          {percent !== undefined ? `${Math.round(percent)}%` : "Processing..."}
+        </span>
+      </div>
+      <Progress value={percent} className="h-2" />
+      <div className="flex items-center justify-between">
+        <span className="text-xs text-muted-foreground">
+          {transfer.filesDone.length} file(s) done
+          {transfer.filesDone.length > 0 && `, last ${transfer.filesDone[transfer.filesDone.length - 1]}`}
+        </span>
+        <Button size="sm" variant="outline" onClick={() => onCancel(transfer.id)}>
+          Cancel
+        </Button>
+      </div>
+    </div>
+  )
+}
+
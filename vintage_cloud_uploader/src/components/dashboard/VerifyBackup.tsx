@@ -0,0 +1,129 @@
+import { invoke } from "@tauri-apps/api/core"
+import {
+  Card,
+  CardContent,
+  CardDescription,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { Button } from "@/components/ui/button"
+import { Input } from "@/components/ui/input"
+import { Label } from "@/components/ui/label"
+import {
+  describeError,
+  describeProblem,
+  type VerifyOptions,
+  type VerifyReport,
+} from "@/lib/types"
+import { useState } from "react"
+
+interface VerifyBackupProps {
+  selectedFolder: string
+}
+
+export function VerifyBackup({ selectedFolder }: VerifyBackupProps) {
+  const [download, setDownload] = useState(false)
+  const [openWorlds, setOpenWorlds] = useState(false)
+  const [useSample, setUseSample] = useState(false)
+  const [sampleSize, setSampleSize] = useState("20")
+  const [verifying, setVerifying] = useState(false)
+  const [report, setReport] = useState<VerifyReport | null>(null)
+  const [error, setError] = useState<string | null>(null)
+
+  const handleVerify = async () => {
+    const sample = Number(sampleSize)
+    if (useSample && !(sample > 0)) {
+      setError("Enter how many objects to check")
+      return
+    }
+    const options: VerifyOptions = {
+      download,
+      open_worlds: openWorlds,
+      sample: useSample ? sample : null,
+    }
+    setVerifying(true)
+    setError(null)
+    setReport(null)
+    try {
+      setReport(await invoke<VerifyReport>("verify_backup", { folderBucket: selectedFolder, options }))
+    } catch (err) {
+      setError(describeError(err))
+    } finally {
+      setVerifying(false)
+    }
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">Verify backup</CardTitle>
+        <CardDescription>
+          Check that every stored copy is there and intact. Nothing is changed.
+        </CardDescription>
+      </CardHeader>
+      <CardContent className="space-y-3">
+        <div className="flex items-center gap-2">
+          <input
+            id="verify-download"
+            type="checkbox"
+            className="h-4 w-4 accent-primary"
+            checked={download}
+            onChange={(event) => setDownload(event.target.checked)}
+          />
+          <Label htmlFor="verify-download">Download and hash every object</Label>
+        </div>
+        <div className="flex items-center gap-2">
+          <input
+            id="verify-open-worlds"
+            type="checkbox"
+            className="h-4 w-4 accent-primary"
+            checked={openWorlds}
+            onChange={(event) => setOpenWorlds(event.target.checked)}
+          />
+          <Label htmlFor="verify-open-worlds">Open worlds and check their database</Label>
+        </div>
+        <div className="flex items-center gap-2">
+          <input
+            id="verify-sample"
+            type="checkbox"
+            className="h-4 w-4 accent-primary"
+            checked={useSample}
+            onChange={(event) => setUseSample(event.target.checked)}
+          />
+          <Label htmlFor="verify-sample">Only check a random sample of</Label>
+          <Input
+            className="w-20"
+            value={sampleSize}
+            disabled={!useSample}
+            onChange={(event) => setSampleSize(event.target.value)}
+          />
+        </div>
+        <Button onClick={handleVerify} disabled={!selectedFolder || verifying}>
+          {verifying ? "Verifying..." : "Verify"}
+        </Button>
+        {report && (
+          <div className="space-y-1">
+            <p className={report.findings.length === 0 ? "text-xs text-muted-foreground" : "text-xs text-destructive"}>
+              Checked {report.checked} of {report.total} objects: {report.findings.length === 0
+                ? "all intact"
+                : `${report.findings.length} problem${report.findings.length === 1 ? "" : "s"}`}
+            </p>
+            {report.findings.map((finding) => (
+              <p key={`${finding.profile}/${finding.key}`} className="text-xs text-muted-foreground">
+                {finding.profile}: {finding.key}: {describeProblem(finding.problem)}
+              </p>
+            ))}
+            {report.without_hash > 0 && (
+              <p className="text-xs text-yellow-600">
+                {report.without_hash} objects have no recorded hash; upload them again to check their content
+              </p>
+            )}
+          </div>
+        )}
+        {error && (
+          <p className="text-xs text-destructive">{error}</p>
+        )}
+      </CardContent>
+    </Card>
+  )
+}
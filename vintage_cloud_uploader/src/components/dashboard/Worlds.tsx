@@ -0,0 +1,71 @@
+import {
+  Card,
+  CardContent,
+  CardHeader,
+  CardTitle,
+} from "@/components/ui/card"
+import { useManifest } from "@/components/context/manifestContext"
+import type { FileInfo, UploadRecord } from "@/lib/types"
+
+interface WorldsProps {
+  selectedFolder: string
+}
+
+function describeUpload(upload: UploadRecord): string {
+  return `Last uploaded by ${upload.device_name} at ${new Date(upload.uploaded_at * 1000).toLocaleString()}`
+}
+
+// Storage profiles whose copy lacks the latest upload.
+function behindProfiles(info: FileInfo): string[] {
+  return Object.entries(info.destinations ?? {})
+    .filter(([, status]) => status.error || status.last_upload?.uploaded_at !== info.last_upload?.uploaded_at)
+    .map(([profile]) => profile)
+}
+
+export function Worlds({ selectedFolder }: WorldsProps) {
+  const { manifest } = useManifest()
+
+  const files = manifest?.all_file_info?.[selectedFolder]?.files ?? {}
+  const checkedOut = manifest?.checked_out?.[selectedFolder] ?? []
+  const worlds = Object.entries(files)
+    .filter(([key]) => key.endsWith(".vcdbs") && !key.startsWith("_"))
+    .sort(([a], [b]) => a.localeCompare(b))
+
+  if (worlds.length === 0) {
+    return null
+  }
+
+  return (
+    <Card>
+      <CardHeader className="pb-4">
+        <CardTitle className="text-lg">Worlds</CardTitle>
+      </CardHeader>
+      <CardContent className="space-y-2">
+        {worlds.map(([key, info]) => (
+          <div key={key} className="flex items-center justify-between gap-2">
+            <div>
+              <p className="text-sm font-medium">
+                {info.world_name}
+                {checkedOut.includes(key) && (
+                  <span className="ml-2 text-xs text-primary">checked out here</span>
+                )}
+              </p>
+              <p className="text-xs text-muted-foreground">
+                {info.last_upload ? describeUpload(info.last_upload) : "Uploader unknown"}
+              </p>
+              {behindProfiles(info).length > 0 && (
+                <p className="text-xs text-yellow-600">
+                  Not yet in {behindProfiles(info).join(", ")}; the next upload retries
+                </p>
+              )}
+            </div>
+            <div className="text-right text-xs text-muted-foreground">
+              <p>{Math.round((info.file_size ?? 0) / 1024 / 1024)} MB</p>
+              {info.game_version && <p>v{info.game_version}</p>}
+            </div>
+          </div>
+        ))}
+      </CardContent>
+    </Card>
+  )
+}
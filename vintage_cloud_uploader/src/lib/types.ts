@@ -1,15 +1,400 @@
+export interface ModRequirement {
+    mod_id: string,
+    version: string,
+}
+
 export interface FileInfo {
     world_name: string,
     playtime: number, // in seconds
     file_size: number | null, // file size in bytes for change detection
+    required_mods?: ModRequirement[], // mods the save lists, read when the world was uploaded
+    game_version?: string, // game version that last saved the world
+    last_upload?: UploadRecord, // device that last uploaded the file
+    destinations?: Record<string, DestinationStatus>, // the copy in each storage profile
+    uploaded_size?: number, // size of what was last uploaded, e.g. a snapshot of an open world
+    sha256?: string, // SHA-256 of what was last uploaded, in hex
+}
+
+export interface UploadRecord {
+    device_id: string,
+    device_name: string,
+    uploaded_at: number, // unix seconds
+}
+
+// The copy of a file in one storage profile
+export interface DestinationStatus {
+    last_upload?: UploadRecord, // the upload the copy comes from
+    error?: string, // why the latest upload there failed
+}
+
+export interface DeviceIdentity {
+    id: string,
+    name: string, // shown to the other devices
 }
 
 export interface UploadManifest {
     files: Record<string, FileInfo>,
 }
 
+export type SyncScope =
+    | "saves"
+    | "mods"
+    | "mod_config"
+    | "client_settings"
+    | "screenshots"
+    | "backups"
+    | "server_config"
+    | "playerdata"
+    | "server_logs"
+
+export const SYNC_SCOPES: { scope: SyncScope, label: string }[] = [
+    { scope: "saves", label: "Saves" },
+    { scope: "mods", label: "Mods" },
+    { scope: "mod_config", label: "Mod config" },
+    { scope: "client_settings", label: "Client settings" },
+    { scope: "screenshots", label: "Screenshots" },
+    { scope: "backups", label: "Backups" },
+    { scope: "server_config", label: "Server config" },
+    { scope: "playerdata", label: "Server player data" },
+    { scope: "server_logs", label: "Server logs" },
+]
+
+export interface SyncFilter {
+    include: string[],
+    exclude: string[],
+}
+
+export interface FolderSettings {
+    scopes: SyncScope[],
+    filters: Partial<Record<SyncScope, SyncFilter>>,
+    schedules?: Schedule[],
+}
+
+export type ScheduleRule =
+    | { kind: "interval", minutes: number }
+    | { kind: "cron", expression: string } // five-field cron in local time
+
+export type ScheduledAction = "upload" | "snapshot"
+
+export interface Schedule {
+    action: ScheduledAction,
+    rule: ScheduleRule,
+    only_while_game_running: boolean,
+    last_run: number | null, // unix seconds
+}
+
 export interface VintageProgramData {
     last_opened: number,
     current_used_bucket: string,
     all_file_info: Record<string, UploadManifest>,
-}
\ No newline at end of file
+    folder_settings: Record<string, FolderSettings>,
+    auto_sync: AutoSyncSettings,
+    profile?: DataProfile,
+    hooks?: HookSettings,
+    device?: DeviceIdentity,
+    locking?: LockSettings,
+    checked_out?: Record<string, string[]>, // worlds this device has checked out, per folder
+}
+
+export interface LockSettings {
+    lease_minutes: number, // minutes before an unrenewed lease can be broken
+}
+
+export type StorageProvider = "b2" | "s3_compatible"
+
+// The bucket and its credentials; the application key lives in the system keyring.
+export interface StorageConfig {
+    provider: StorageProvider,
+    bucket: string,
+    region: string, // empty uses the provider's default
+    endpoint: string, // empty uses B2's endpoint for the region, or AWS
+    key_id: string,
+    application_key: string, // never sent to the frontend; empty keeps the stored key
+    plaintext_key_fallback: boolean, // keep the key in config.json when the system keyring is unavailable
+}
+
+// Server-side encryption asked for when storing objects
+export type Encryption =
+    | { kind: "none" } // the bucket's default
+    | { kind: "aes256" } // keys managed by the storage (SSE-S3, SSE-B2)
+    | { kind: "kms", key_id: string } // a key in AWS KMS
+
+// A named storage; the storage fields are flattened into it
+export interface StorageProfile extends StorageConfig {
+    name: string,
+    prefix: string, // put in front of every folder's prefix; empty for none
+    encryption: Encryption,
+}
+
+// The profiles a folder is stored in; files are read from the first one
+export interface Binding {
+    profiles: string[], // empty uses the default profile
+    worlds: Record<string, string[]>, // per world manifest key, e.g. "server.vcdbs"
+}
+
+// The user's config.json in the config directory
+export interface AppConfig {
+    profiles: StorageProfile[], // the first one is the default
+    folder_buckets: string[], // offered besides the folders synced before
+    prefixes: Record<string, string>, // key prefix per folder; the folder name when missing
+    bindings: Record<string, Binding>, // per folder
+    retry: RetrySettings,
+}
+
+export interface ConfigView {
+    config: AppConfig,
+    profiles_with_key: string[],
+}
+
+// How storage requests failing for a temporary reason are retried
+export interface RetrySettings {
+    max_attempts: number, // the first attempt included
+    initial_delay_ms: number, // doubles with every further attempt
+    max_delay_secs: number, // unless the storage asks for more with Retry-After
+}
+
+// Shell commands run around syncs; a failing pre hook cancels the sync
+export interface HookSettings {
+    pre_upload: string | null,
+    post_upload: string | null,
+    pre_download: string | null,
+    post_download: string | null,
+}
+
+export interface ServerProfile {
+    data_path: string, // the server's --dataPath
+    pre_backup_command: string | null, // run before snapshots, e.g. to send /autosavenow
+    pre_backup_wait_secs: number, // wait this long after the command for the save to finish
+}
+
+export type DataProfile =
+    | { kind: "client" }
+    | ({ kind: "server" } & ServerProfile)
+
+export interface AutoSyncSettings {
+    upload_on_exit: boolean, // upload the current folder after the game exits
+    upload_delay_secs: number, // wait this long after the exit before uploading
+    download_before_launch: boolean, // download the latest saves when launching from here
+    watch_saves: boolean, // upload worlds as they change on disk
+    quiet_period_secs: number, // seconds without writes before changed worlds are uploaded
+}
+
+export type ModMismatch =
+    | { kind: "missing", mod_id: string, required_version: string }
+    | { kind: "version_mismatch", mod_id: string, required_version: string, installed_version: string }
+
+export type DownloadWarning =
+    | { kind: "mods", world: string, mismatch: ModMismatch }
+    | { kind: "newer_game_version", world: string, saved_with: string, installed: string }
+
+export function describeDownloadWarning(warning: DownloadWarning): string {
+    switch (warning.kind) {
+        case "mods": {
+            const { mismatch } = warning
+            return mismatch.kind === "missing"
+                ? `${warning.world}: mod ${mismatch.mod_id} ${mismatch.required_version} is not installed`
+                : `${warning.world}: mod ${mismatch.mod_id} is ${mismatch.installed_version} locally but the world was saved with ${mismatch.required_version}`
+        }
+        case "newer_game_version":
+            return `${warning.world}: saved with game version ${warning.saved_with} but ${warning.installed} is installed`
+    }
+}
+
+export type SyncDirection = "upload" | "download"
+
+export type PlanAction = "upload" | "download" | "skip" | "conflict"
+
+export type PlanReason =
+    | "new_file"
+    | "size_changed"
+    | "no_size_recorded"
+    | "requested"
+    | "unchanged"
+    | "missing_locally"
+    | "remote_differs"
+    | "changed_on_both_sides"
+    | "changed_in_cloud"
+    | "deleted_locally"
+    | "missing_from_profile"
+
+export const PLAN_REASONS: Record<PlanReason, string> = {
+    new_file: "not uploaded before",
+    size_changed: "size changed since the last sync",
+    no_size_recorded: "no size recorded at the last sync",
+    requested: "written to while watched",
+    unchanged: "unchanged",
+    missing_locally: "not on this device",
+    remote_differs: "differs from the cloud copy",
+    changed_on_both_sides: "changed here and in the cloud since the last sync",
+    changed_in_cloud: "changed in the cloud; download it first",
+    deleted_locally: "deleted here; the cloud copy is kept",
+    missing_from_profile: "not yet in every storage profile it goes to",
+}
+
+export interface PlannedFile {
+    key: string,
+    action: PlanAction,
+    reason: PlanReason,
+    size: number,
+}
+
+export interface SyncPlan {
+    folder_bucket: string,
+    direction: SyncDirection,
+    files: PlannedFile[],
+    warnings: DownloadWarning[],
+}
+
+export function describePlan(plan: SyncPlan): string {
+    const transfers = plan.files.filter((file) => file.action === plan.direction)
+    const count = (action: PlanAction) => plan.files.filter((file) => file.action === action).length
+    const megabytes = transfers.reduce((total, file) => total + file.size, 0) / 1024 / 1024
+    const parts = [`${count(plan.direction)} to ${plan.direction} (${megabytes.toFixed(1)} MB)`]
+    const conflicts = count("conflict")
+    if (conflicts > 0) {
+        parts.push(`${conflicts} conflict${conflicts === 1 ? "" : "s"}`)
+    }
+    parts.push(`${count("skip")} skipped`)
+    return parts.join(", ")
+}
+
+export interface VerifyOptions {
+    download: boolean, // fetch each object and hash it instead of trusting its metadata
+    open_worlds: boolean, // run SQLite's integrity check on downloaded worlds
+    sample: number | null, // check this many random objects instead of all
+}
+
+export type Problem =
+    | { kind: "missing" }
+    | { kind: "wrong_size", expected: number, actual: number }
+    | { kind: "wrong_hash", expected: string, actual: string }
+    | { kind: "unreadable_world", error: string }
+    | { kind: "orphaned", size: number }
+    | { kind: "unchecked", error: string }
+
+export function describeProblem(problem: Problem): string {
+    switch (problem.kind) {
+        case "missing":
+            return "missing"
+        case "wrong_size":
+            return `corrupt: ${problem.actual} bytes instead of ${problem.expected}`
+        case "wrong_hash":
+            return `corrupt: SHA-256 ${problem.actual} instead of ${problem.expected}`
+        case "unreadable_world":
+            return `world doesn't open: ${problem.error}`
+        case "orphaned":
+            return `orphaned (${problem.size} bytes, in no manifest)`
+        case "unchecked":
+            return `not checked: ${problem.error}`
+    }
+}
+
+export interface Finding {
+    profile: string,
+    key: string, // relative to the folder bucket
+    problem: Problem,
+}
+
+export interface VerifyReport {
+    folder_bucket: string,
+    checked: number,
+    total: number,
+    without_hash: number, // checked objects uploaded before hashes were recorded
+    findings: Finding[],
+}
+
+export interface GameStatus {
+    running: boolean,
+    open_worlds: string[], // file names of worlds the game has open
+}
+
+export interface TransferProgress {
+    transferred_bytes: number,
+    total_bytes: number,
+    current_file: string,
+    elapsed_secs: number,
+    file_done: boolean, // set once current_file is completely transferred
+}
+
+// What a failed command or transfer reports, tagged with its category
+export type ErrorKind =
+    | "config"
+    | "auth"
+    | "network"
+    | "not_found"
+    | "conflict"
+    | "needs_confirmation"
+    | "integrity"
+    | "storage"
+    | "local_io"
+    | "incomplete" // some files failed, the others were transferred
+    | "cancelled"
+    | "other"
+
+export interface SyncError {
+    kind: ErrorKind,
+    message: string,
+    retryable: boolean, // trying again later may succeed
+    hint: string | null, // what the user can do about it
+}
+
+// Commands reject with a SyncError; anything else is wrapped as kind "other"
+export function toSyncError(err: unknown): SyncError {
+    if (typeof err === "object" && err !== null && "kind" in err && "message" in err) {
+        return err as SyncError
+    }
+    const message = err instanceof Error ? err.message : String(err)
+    return { kind: "other", message, retryable: false, hint: null }
+}
+
+// e.g. "failed to upload world.vcdbs: SignatureDoesNotMatch. Check the key ID and ..."
+export function describeError(err: unknown): string {
+    const error = toSyncError(err)
+    return error.hint ? `${error.message}. ${error.hint}` : error.message
+}
+
+// Payloads of the "sync://" events, each tagged with the transfer it belongs to
+export interface SyncProgressEvent extends TransferProgress {
+    transfer_id: number,
+    direction: SyncDirection,
+    folder_bucket: string,
+}
+
+export interface SyncFileDoneEvent {
+    transfer_id: number,
+    file: string,
+}
+
+export interface SyncErrorEvent {
+    transfer_id: number,
+    error: SyncError, // kind "cancelled" when the transfer was cancelled
+}
+
+export interface SyncFinishedEvent {
+    transfer_id: number,
+}
+
+// e.g. "world.vcdbs (42% @ 3.10 MB/s, 1:05 left)"
+export function describeProgress(progress: TransferProgress): string {
+    const percent = progress.total_bytes > 0
+        ? Math.round((progress.transferred_bytes / progress.total_bytes) * 100)
+        : 0
+    const speed = progress.elapsed_secs > 0 ? progress.transferred_bytes / progress.elapsed_secs : 0
+    let eta = ""
+    if (speed > 0) {
+        const secs = Math.round((progress.total_bytes - progress.transferred_bytes) / speed)
+        eta = `, ${Math.floor(secs / 60)}:${String(secs % 60).padStart(2, "0")} left`
+    }
+    return `${progress.current_file} (${percent}% @ ${(speed / 1024 / 1024).toFixed(2)} MB/s${eta})`
+}
+
+// Payload of the "auto-sync" event
+export type WatcherEvent =
+    | { kind: "game_started" }
+    | { kind: "game_stopped", upload_in_secs: number }
+    | { kind: "worlds_changed", worlds: string[] }
+    | { kind: "scheduled_run", folder_bucket: string, action: ScheduledAction }
+    | { kind: "upload_started" }
+    | ({ kind: "upload_progress" } & TransferProgress)
+    | { kind: "upload_finished" }
+    | { kind: "upload_failed", error: string }
@@ -0,0 +1,58 @@
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::error::Error;
+
+/// How often a running request checks whether it was cancelled.
+const CANCEL_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Lets a UI stop a transfer it started. Clones share the same flag, so the
+/// UI keeps one and hands the other to the transfer.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken {
+    cancelled: Arc<AtomicBool>,
+}
+
+impl CancelToken {
+    pub fn new() -> CancelToken {
+        CancelToken::default()
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Fails with `Error::Cancelled` once the token is cancelled.
+    pub fn check(&self) -> Result<(), Error> {
+        if self.is_cancelled() {
+            Err(Error::Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Runs `future` until it finishes or the token is cancelled. A
+    /// cancelled future is dropped, which aborts the request it was making.
+    pub async fn run<T, F>(&self, future: F) -> Result<T, Error>
+    where
+        F: Future<Output = Result<T, Error>>,
+    {
+        self.check()?;
+        tokio::select! {
+            result = future => result,
+            _ = self.wait_cancelled() => Err(Error::Cancelled),
+        }
+    }
+
+    async fn wait_cancelled(&self) {
+        while !self.is_cancelled() {
+            tokio::time::sleep(CANCEL_CHECK_INTERVAL).await;
+        }
+    }
+}
@@ -0,0 +1,500 @@
+use aws_sdk_s3::operation::put_object::builders::PutObjectFluentBuilder;
+use aws_sdk_s3::types::ServerSideEncryption;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::credentials::{forget_application_key, store_application_key, stored_application_key};
+use crate::error::Error;
+use crate::helper_functions::resolve_config_dir;
+use crate::manifest_info::get_manifest_info;
+use crate::retry::RetrySettings;
+
+const CONFIG_FILE_NAME: &str = "config.json";
+
+/// Name of the profile used when none is configured, and of the one older
+/// configurations with a single storage are turned into.
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Settings the user edits: where the saves go and how. Read from
+/// `config.json` in the config directory on every use, so changes made in a
+/// UI or by hand apply to the next transfer without a restart.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppConfig {
+    /// Named places to store saves in. The first one is the default for
+    /// folder buckets without a binding.
+    pub profiles: Vec<StorageProfile>,
+    /// Folder buckets offered in the UIs besides those already synced.
+    pub folder_buckets: Vec<String>,
+    /// Key prefix in the bucket per folder bucket; the folder bucket's own
+    /// name when missing.
+    pub prefixes: HashMap<String, String>,
+    /// Which profiles each folder bucket, or a world in it, is stored in.
+    pub bindings: HashMap<String, Binding>,
+    pub retry: RetrySettings,
+    /// The single storage of older versions; moved into `profiles` on load.
+    #[serde(skip_serializing)]
+    storage: Option<StorageConfig>,
+}
+
+/// A named storage: a bucket with its credentials, where in it the saves go
+/// and how they are encrypted there.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageProfile {
+    pub name: String,
+    #[serde(flatten)]
+    pub storage: StorageConfig,
+    /// Put in front of every folder bucket's prefix, so several groups can
+    /// share one bucket. Empty stores folder buckets at the top.
+    pub prefix: String,
+    pub encryption: Encryption,
+}
+
+impl StorageProfile {
+    /// Where `folder_bucket`, stored under `folder_prefix`, lives in this
+    /// profile's bucket.
+    pub fn key_prefix(&self, folder_prefix: &str) -> String {
+        match self.prefix.trim().trim_matches('/') {
+            "" => folder_prefix.to_string(),
+            prefix => format!("{}/{}", prefix, folder_prefix),
+        }
+    }
+}
+
+/// Server-side encryption asked for when storing objects. Reading them
+/// back needs nothing extra.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Encryption {
+    /// Whatever the bucket does by default.
+    #[default]
+    None,
+    /// AES-256 with keys managed by the storage (SSE-S3, or SSE-B2 on B2).
+    Aes256,
+    /// A key in AWS KMS, for buckets on AWS.
+    Kms { key_id: String },
+}
+
+impl Encryption {
+    pub(crate) fn apply(&self, request: PutObjectFluentBuilder) -> PutObjectFluentBuilder {
+        match self {
+            Encryption::None => request,
+            Encryption::Aes256 => request.server_side_encryption(ServerSideEncryption::Aes256),
+            Encryption::Kms { key_id } => request
+                .server_side_encryption(ServerSideEncryption::AwsKms)
+                .ssekms_key_id(key_id),
+        }
+    }
+}
+
+/// The profiles a folder bucket is stored in.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Binding {
+    /// Profiles the folder bucket's files go to, the first one read from.
+    /// Empty uses the default profile.
+    pub profiles: Vec<String>,
+    /// Worlds stored in other profiles than the rest of the folder bucket,
+    /// by manifest key. Worlds have no scope prefix, e.g. `server.vcdbs`.
+    pub worlds: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StorageProvider {
+    #[default]
+    B2,
+    /// Any other storage speaking the S3 API, e.g. AWS, MinIO or a NAS.
+    S3Compatible,
+}
+
+impl StorageProvider {
+    pub const ALL: [StorageProvider; 2] = [StorageProvider::B2, StorageProvider::S3Compatible];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StorageProvider::B2 => "Backblaze B2",
+            StorageProvider::S3Compatible => "S3-compatible",
+        }
+    }
+}
+
+/// The bucket and the credentials for it. The application key is kept in
+/// the system keyring, not in this struct's file, unless
+/// `plaintext_key_fallback` allows it.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StorageConfig {
+    pub provider: StorageProvider,
+    pub bucket: String,
+    /// Empty uses the provider's default region.
+    pub region: String,
+    /// Empty uses B2's endpoint for the region, or AWS for S3-compatible
+    /// storage.
+    pub endpoint: String,
+    pub key_id: String,
+    /// Only set in the file when the keyring was unavailable and
+    /// `plaintext_key_fallback` is on.
+    pub application_key: String,
+    /// Keep the application key in the config file when the system keyring
+    /// can't be used. Off unless the user opts in, as the file is plain text.
+    pub plaintext_key_fallback: bool,
+}
+
+impl StorageConfig {
+    /// The region to sign requests for.
+    pub fn region(&self) -> String {
+        match (self.region.trim(), self.provider) {
+            ("", StorageProvider::B2) => "us-west-000".to_string(),
+            ("", StorageProvider::S3Compatible) => "us-east-1".to_string(),
+            (region, _) => region.to_string(),
+        }
+    }
+
+    /// The endpoint to send requests to; `None` lets the SDK pick AWS's.
+    pub fn endpoint(&self) -> Option<String> {
+        match (self.endpoint.trim(), self.provider) {
+            ("", StorageProvider::B2) => Some(format!("https://s3.{}.backblazeb2.com", self.region())),
+            ("", StorageProvider::S3Compatible) => None,
+            (endpoint, _) => Some(endpoint.to_string()),
+        }
+    }
+
+    /// The application key for `key_id`: the one in the file, else the one
+    /// in the keyring.
+    fn stored_key(&self) -> Result<Option<String>, Error> {
+        if !self.application_key.is_empty() {
+            return Ok(Some(self.application_key.clone()));
+        }
+        stored_application_key(&self.key_id)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        let endpoint = self.endpoint.trim();
+        if !endpoint.is_empty() && !endpoint.starts_with("https://") && !endpoint.starts_with("http://") {
+            return Err(Error::Config(format!(
+                "the endpoint {} must start with https:// or http://",
+                endpoint
+            )));
+        }
+        Ok(())
+    }
+}
+
+impl AppConfig {
+    /// A copy without the application keys, for sending to a UI. Saving it
+    /// back with `set_config` keeps the stored keys.
+    pub fn redacted(&self) -> AppConfig {
+        let mut config = self.clone();
+        for profile in &mut config.profiles {
+            profile.storage.application_key.clear();
+        }
+        config
+    }
+
+    /// The profiles with an application key stored, in the file or the
+    /// keyring.
+    pub fn profiles_with_key(&self) -> Vec<String> {
+        self.profiles
+            .iter()
+            .filter(|profile| matches!(profile.storage.stored_key(), Ok(Some(_))))
+            .map(|profile| profile.name.clone())
+            .collect()
+    }
+
+    pub fn default_profile(&self) -> &str {
+        self.profiles.first().map_or(DEFAULT_PROFILE, |profile| profile.name.as_str())
+    }
+
+    /// The profile called `name`. Without any profile configured, the
+    /// default one is empty, so connecting asks for storage settings.
+    pub fn profile(&self, name: &str) -> Result<StorageProfile, Error> {
+        if let Some(profile) = self.profiles.iter().find(|profile| profile.name == name) {
+            return Ok(profile.clone());
+        }
+        if self.profiles.is_empty() && name == DEFAULT_PROFILE {
+            return Ok(StorageProfile {
+                name: DEFAULT_PROFILE.to_string(),
+                ..StorageProfile::default()
+            });
+        }
+        Err(Error::Config(format!("there is no storage profile called {}", name)))
+    }
+
+    /// The profiles the file with manifest key `key` of `folder_bucket` is
+    /// stored in; it is read from the first one.
+    pub fn destinations(&self, folder_bucket: &str, key: &str) -> Vec<String> {
+        let binding = self.bindings.get(folder_bucket);
+        let bound = binding
+            .and_then(|binding| binding.worlds.get(key))
+            .filter(|profiles| !profiles.is_empty())
+            .or_else(|| binding.map(|binding| &binding.profiles).filter(|profiles| !profiles.is_empty()));
+        match bound {
+            Some(profiles) => profiles.clone(),
+            None => vec![self.default_profile().to_string()],
+        }
+    }
+
+    /// Every profile holding some of `folder_bucket`, the folder bucket's
+    /// own first.
+    pub fn folder_profiles(&self, folder_bucket: &str) -> Vec<String> {
+        let mut profiles = self.destinations(folder_bucket, "");
+        for world_profiles in self.bindings.get(folder_bucket).into_iter().flat_map(|binding| binding.worlds.values()) {
+            for profile in world_profiles {
+                if !profiles.contains(profile) {
+                    profiles.push(profile.clone());
+                }
+            }
+        }
+        profiles
+    }
+
+    /// The key prefix `folder_bucket` is stored under.
+    pub fn prefix(&self, folder_bucket: &str) -> String {
+        self.prefixes
+            .get(folder_bucket)
+            .map(|prefix| prefix.trim().trim_matches('/'))
+            .filter(|prefix| !prefix.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| folder_bucket.to_string())
+    }
+
+    /// Moves the single storage of older versions into a profile.
+    fn migrate_legacy_storage(&mut self) {
+        if let Some(storage) = self.storage.take() {
+            if self.profiles.is_empty() {
+                self.profiles.push(StorageProfile {
+                    name: DEFAULT_PROFILE.to_string(),
+                    storage,
+                    ..StorageProfile::default()
+                });
+            }
+        }
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        let mut names = BTreeSet::new();
+        for profile in &self.profiles {
+            if profile.name.trim().is_empty() {
+                return Err(Error::Config("every storage profile needs a name".to_string()));
+            }
+            if !names.insert(profile.name.as_str()) {
+                return Err(Error::Config(format!("there are two storage profiles called {}", profile.name)));
+            }
+            if matches!(&profile.encryption, Encryption::Kms { key_id } if key_id.trim().is_empty()) {
+                return Err(Error::Config(format!("the profile {} needs a KMS key ID", profile.name)));
+            }
+            profile.storage.validate()?;
+        }
+        for (folder_bucket, binding) in &self.bindings {
+            if let Some(world) = binding.worlds.keys().find(|world| world.starts_with("Saves/")) {
+                return Err(Error::Config(format!(
+                    "bind the world {} of {} without Saves/, as {}",
+                    world,
+                    folder_bucket,
+                    world.trim_start_matches("Saves/")
+                )));
+            }
+            let bound = binding.profiles.iter().chain(binding.worlds.values().flatten());
+            for profile in bound {
+                self.profile(profile).map_err(|_| {
+                    Error::Config(format!("{} is bound to the unknown storage profile {}", folder_bucket, profile))
+                })?;
+            }
+        }
+        Ok(())
+    }
+}
+
+pub fn config_file_path() -> Result<PathBuf, Error> {
+    Ok(resolve_config_dir()?.join(CONFIG_FILE_NAME))
+}
+
+/// The configuration file's contents, or the defaults when there is none yet.
+pub fn load_config() -> Result<AppConfig, Error> {
+    let path = config_file_path()?;
+    if !path.exists() {
+        return Ok(AppConfig::default());
+    }
+    let data = fs::read_to_string(&path)
+        .map_err(|e| io::Error::new(e.kind(), format!("Failed to read {}: {}", path.display(), e)))?;
+    let mut config: AppConfig =
+        serde_json::from_str(&data).map_err(|e| Error::Config(format!("Failed to parse {}: {}", path.display(), e)))?;
+    config.migrate_legacy_storage();
+    Ok(config)
+}
+
+/// Replaces the configuration. A new application key goes to the keyring;
+/// an empty one keeps the stored key, so a UI showing `redacted` settings
+/// never has to know it. A key left in the file is moved to the keyring
+/// unless `plaintext_key_fallback` is on and the keyring is unavailable.
+pub fn set_config(mut config: AppConfig) -> Result<(), Error> {
+    config.migrate_legacy_storage();
+    config.validate()?;
+    let previous = load_config()?.profiles;
+    for profile in &mut config.profiles {
+        let storage = &mut profile.storage;
+        let mut application_key = std::mem::take(&mut storage.application_key);
+        if application_key.is_empty() {
+            // Keep the profile's key, following it to a new key ID when
+            // only the ID was edited.
+            let before = previous.iter().find(|before| before.name == profile.name);
+            application_key = match before {
+                Some(before) if before.storage.key_id == storage.key_id => before.storage.application_key.clone(),
+                Some(before) => before.storage.stored_key()?.unwrap_or_default(),
+                None => String::new(),
+            };
+        }
+        storage.application_key = key_for_file(storage, application_key)?;
+    }
+    for before in &previous {
+        if !config.profiles.iter().any(|profile| profile.storage.key_id == before.storage.key_id) {
+            // Best effort: a leftover entry only wastes a keyring slot.
+            let _ = forget_application_key(&before.storage.key_id);
+        }
+    }
+    config.folder_buckets.retain(|folder_bucket| !folder_bucket.trim().is_empty());
+    config.prefixes.retain(|_, prefix| !prefix.trim().is_empty());
+    config
+        .bindings
+        .retain(|_, binding| !binding.profiles.is_empty() || !binding.worlds.is_empty());
+
+    let path = config_file_path()?;
+    let data = serde_json::to_string_pretty(&config).map_err(|e| Error::other(format!("Failed to save the config: {}", e)))?;
+    fs::write(&path, data).map_err(|e| io::Error::new(e.kind(), format!("Failed to save {}: {}", path.display(), e)))?;
+    // The file may hold application keys, so only the user may read it.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o600))?;
+    }
+    Ok(())
+}
+
+/// Stores `application_key` in the keyring and returns what the config file
+/// keeps of it: nothing, or the key itself when the keyring failed and the
+/// plaintext fallback is allowed.
+fn key_for_file(storage: &StorageConfig, application_key: String) -> Result<String, Error> {
+    if application_key.is_empty() {
+        return Ok(application_key);
+    }
+    match store_application_key(&storage.key_id, &application_key) {
+        Ok(()) => Ok(String::new()),
+        Err(err) if storage.plaintext_key_fallback => {
+            println!("Keeping the application key in the config file: {}", err);
+            Ok(application_key)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// Remembers a folder bucket so the UIs offer it before its first upload.
+pub fn add_folder_bucket(folder_bucket: &str) -> Result<(), Error> {
+    let folder_bucket = folder_bucket.trim();
+    if folder_bucket.is_empty() {
+        return Err(Error::Config("the folder name can't be empty".to_string()));
+    }
+    let mut config = load_config()?;
+    if !config.folder_buckets.iter().any(|known| known == folder_bucket) {
+        config.folder_buckets.push(folder_bucket.to_string());
+        set_config(config)?;
+    }
+    Ok(())
+}
+
+/// The folder buckets to offer: those in the configuration and those
+/// synced before, sorted.
+pub fn known_folder_buckets() -> Result<Vec<String>, Error> {
+    let mut folder_buckets: BTreeSet<String> = load_config()?.folder_buckets.into_iter().collect();
+    folder_buckets.extend(get_manifest_info()?.all_file_info.into_keys());
+    Ok(folder_buckets.into_iter().collect())
+}
+
+/// Completes `storage`, e.g. settings being edited, for connecting: an empty
+/// application key is looked up for its key ID.
+pub fn resolve_storage(storage: StorageConfig) -> Result<StorageConfig, Error> {
+    complete_storage(&load_config()?, storage)
+}
+
+/// `resolve_storage` with `config` already loaded.
+pub(crate) fn complete_storage(config: &AppConfig, mut storage: StorageConfig) -> Result<StorageConfig, Error> {
+    storage.validate()?;
+    let mut keyring_failure = None;
+    if storage.application_key.is_empty() {
+        let in_file = config
+            .profiles
+            .iter()
+            .map(|profile| &profile.storage)
+            .find(|in_file| in_file.key_id == storage.key_id && !in_file.application_key.is_empty());
+        let stored = match in_file {
+            Some(in_file) => in_file.stored_key(),
+            None => stored_application_key(&storage.key_id),
+        };
+        match stored {
+            Ok(key) => storage.application_key = key.unwrap_or_default(),
+            Err(err) => keyring_failure = Some(err),
+        }
+    }
+    for (value, what) in [(&storage.bucket, "bucket"), (&storage.key_id, "key ID")] {
+        if value.trim().is_empty() {
+            return Err(Error::Config(format!("no storage {} configured", what)));
+        }
+    }
+    if storage.application_key.is_empty() {
+        return Err(keyring_failure.unwrap_or_else(|| Error::Config("no storage application key configured".to_string())));
+    }
+    Ok(storage)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(name: &str) -> StorageProfile {
+        StorageProfile {
+            name: name.to_string(),
+            ..StorageProfile::default()
+        }
+    }
+
+    fn config(worlds: &[(&str, &str)]) -> AppConfig {
+        let binding = Binding {
+            profiles: vec!["personal".to_string(), "nas".to_string()],
+            worlds: worlds
+                .iter()
+                .map(|(world, profile)| (world.to_string(), vec![profile.to_string()]))
+                .collect(),
+        };
+        AppConfig {
+            profiles: vec![profile("personal"), profile("team"), profile("nas")],
+            bindings: [("server".to_string(), binding)].into_iter().collect(),
+            ..AppConfig::default()
+        }
+    }
+
+    #[test]
+    fn world_bindings_override_the_folder_binding() {
+        let config = config(&[("shared.vcdbs", "team")]);
+        assert_eq!(config.destinations("server", "shared.vcdbs"), ["team"]);
+        assert_eq!(config.destinations("server", "solo.vcdbs"), ["personal", "nas"]);
+        assert_eq!(config.destinations("server", "_mods/mod.zip"), ["personal", "nas"]);
+        assert_eq!(config.destinations("other", "shared.vcdbs"), ["personal"]);
+        assert_eq!(config.folder_profiles("server"), ["personal", "nas", "team"]);
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn rejects_world_bindings_with_the_saves_folder() {
+        let config = config(&[("Saves/shared.vcdbs", "team")]);
+        assert!(matches!(config.validate(), Err(Error::Config(message)) if message.contains("as shared.vcdbs")));
+    }
+
+    #[test]
+    fn rejects_bindings_to_unknown_profiles() {
+        assert!(matches!(config(&[("shared.vcdbs", "office")]).validate(), Err(Error::Config(_))));
+    }
+}
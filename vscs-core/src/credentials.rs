@@ -0,0 +1,54 @@
+use keyring::Entry;
+
+use crate::error::Error;
+
+/// Service name the application keys are stored under in the keyring.
+const KEYRING_SERVICE: &str = "vintage-story-cloud-save";
+
+/// The keyring entry holding the application key for `key_id`.
+fn entry(key_id: &str) -> Result<Entry, Error> {
+    Entry::new(KEYRING_SERVICE, key_id).map_err(keyring_error)
+}
+
+fn keyring_error(err: keyring::Error) -> Error {
+    match err {
+        keyring::Error::NoStorageAccess(_) | keyring::Error::PlatformFailure(_) => Error::Config(format!(
+            "the system keyring is unavailable ({}); allow storing the key in the config file instead",
+            err
+        )),
+        _ => Error::other(format!("keyring: {}", err)),
+    }
+}
+
+/// The application key stored in the platform's secret store (Secret
+/// Service on Linux, Keychain on macOS, Credential Manager on Windows) for
+/// `key_id`, if there is one.
+pub fn stored_application_key(key_id: &str) -> Result<Option<String>, Error> {
+    if key_id.is_empty() {
+        return Ok(None);
+    }
+    match entry(key_id)?.get_password() {
+        Ok(key) => Ok(Some(key)),
+        Err(keyring::Error::NoEntry) => Ok(None),
+        Err(err) => Err(keyring_error(err)),
+    }
+}
+
+pub fn store_application_key(key_id: &str, application_key: &str) -> Result<(), Error> {
+    if key_id.is_empty() {
+        return Err(Error::Config("the key ID is needed to store the application key".to_string()));
+    }
+    entry(key_id)?.set_password(application_key).map_err(keyring_error)
+}
+
+/// Removes the application key for `key_id`; nothing happens when none is
+/// stored.
+pub fn forget_application_key(key_id: &str) -> Result<(), Error> {
+    if key_id.is_empty() {
+        return Ok(());
+    }
+    match entry(key_id)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+        Err(err) => Err(keyring_error(err)),
+    }
+}
@@ -0,0 +1,99 @@
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+
+use crate::error::Error;
+use crate::manifest_info::{get_manifest_info, update_program_data};
+
+/// Identifies this machine to the other devices syncing the same folder
+/// bucket. Generated on first use and kept in the program data.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeviceIdentity {
+    pub id: String,
+    /// Shown to the other devices; the host name unless renamed.
+    pub name: String,
+}
+
+impl DeviceIdentity {
+    fn generate() -> DeviceIdentity {
+        // RandomState is seeded from the OS, which is random enough for an
+        // identifier that only has to differ between a handful of PCs.
+        let random = || RandomState::new().build_hasher().finish();
+        DeviceIdentity {
+            id: format!("{:016x}{:016x}", random(), random()),
+            name: sysinfo::System::host_name().unwrap_or_else(|| "Unnamed device".to_string()),
+        }
+    }
+}
+
+/// Who uploaded a file last, and when.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct UploadRecord {
+    pub device_id: String,
+    pub device_name: String,
+    /// Unix time of the upload.
+    pub uploaded_at: i64,
+}
+
+impl UploadRecord {
+    pub fn now(device: &DeviceIdentity) -> UploadRecord {
+        UploadRecord {
+            device_id: device.id.clone(),
+            device_name: device.name.clone(),
+            uploaded_at: Local::now().timestamp(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let when = Local
+            .timestamp_opt(self.uploaded_at, 0)
+            .single()
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "an unknown time".to_string());
+        format!("last uploaded by {} at {}", self.device_name, when)
+    }
+}
+
+/// This machine's identity, generating and storing one on first use.
+pub fn device_identity() -> Result<DeviceIdentity, Error> {
+    let device = get_manifest_info()?.device;
+    if !device.id.is_empty() {
+        return Ok(device);
+    }
+    update_program_data(|program_data| {
+        // Another thread may have generated it in the meantime.
+        if program_data.device.id.is_empty() {
+            program_data.device = DeviceIdentity::generate();
+        }
+        Ok(program_data.device.clone())
+    })
+}
+
+pub fn set_device_name(name: &str) -> Result<(), Error> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(Error::Config("the device name can't be empty".to_string()));
+    }
+    update_program_data(|program_data| {
+        if program_data.device.id.is_empty() {
+            program_data.device = DeviceIdentity::generate();
+        }
+        program_data.device.name = name.to_string();
+        Ok(())
+    })
+}
+
+/// S3 user metadata travels as HTTP headers, so anything outside printable
+/// ASCII in a device name is percent-encoded.
+pub fn metadata_value(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_graphic() || byte == b' ' {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
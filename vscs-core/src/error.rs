@@ -0,0 +1,232 @@
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io;
+use std::time::Duration;
+
+/// Why an operation of vscs-core failed. The variant says what kind of
+/// problem it is, so a UI can tell bad credentials from a network outage and
+/// point the user at the fix (`hint`) or offer to try again (`is_retryable`).
+#[derive(Debug)]
+pub enum Error {
+    /// Settings are missing or invalid, e.g. no bucket configured or a sync
+    /// rule that isn't a valid glob.
+    Config(String),
+    /// The storage rejected the credentials or denied access.
+    Auth(String),
+    /// The storage could not be reached, or the connection broke off.
+    Network(String),
+    /// Something the operation needs is missing, e.g. the bucket, the data
+    /// folder, the game install or any file to sync.
+    NotFound(String),
+    /// Someone else holds what the operation needs: a world checked out by
+    /// another device, or open in the game.
+    Conflict(String),
+    /// The operation would do something the user has to confirm first, e.g.
+    /// replace a world with one needing missing mods.
+    NeedsConfirmation(String),
+    /// Data isn't what it should be, e.g. an unreadable manifest or a
+    /// download cut short.
+    Integrity(String),
+    /// The storage failed a request for another reason. `retryable` when the
+    /// failure is temporary, like throttling or a server error, and
+    /// `retry_after` when the storage said how long to wait.
+    Storage {
+        message: String,
+        retryable: bool,
+        retry_after: Option<Duration>,
+    },
+    /// Reading or writing a local file failed.
+    LocalIo(io::Error),
+    /// Some files of a transfer failed for good while the others made it;
+    /// `failed` holds each failed file's key with its error.
+    Incomplete { failed: Vec<(String, Error)>, total: usize },
+    /// Stopped through its `CancelToken`.
+    Cancelled,
+    /// Anything else, described for the user.
+    Other(String),
+}
+
+impl Error {
+    pub fn other(message: impl fmt::Display) -> Error {
+        Error::Other(message.to_string())
+    }
+
+    /// Classifies a failed request to the bucket. `context` says what was
+    /// being done, e.g. "failed to upload world.vcdbs".
+    pub fn storage<E>(context: impl fmt::Display, err: SdkError<E, HttpResponse>) -> Error
+    where
+        E: ProvideErrorMetadata + std::error::Error + 'static,
+    {
+        let detail = err
+            .message()
+            .or(err.code())
+            .map(str::to_string)
+            .unwrap_or_else(|| err.to_string());
+        let message = format!("{}: {}", context, detail);
+        match &err {
+            SdkError::ConstructionFailure(_) => Error::Config(message),
+            SdkError::TimeoutError(_) | SdkError::DispatchFailure(_) | SdkError::ResponseError(_) => {
+                Error::Network(message)
+            }
+            SdkError::ServiceError(service) => {
+                let status = service.raw().status().as_u16();
+                match (status, err.code()) {
+                    (401 | 403, _)
+                    | (_, Some("InvalidAccessKeyId" | "SignatureDoesNotMatch" | "AccessDenied" | "ExpiredToken")) => {
+                        Error::Auth(message)
+                    }
+                    (404, _) | (_, Some("NoSuchBucket" | "NoSuchKey")) => Error::NotFound(message),
+                    (409 | 412, _) => Error::Conflict(message),
+                    _ => Error::Storage {
+                        message,
+                        retryable: status == 429 || status >= 500,
+                        retry_after: service
+                            .raw()
+                            .headers()
+                            .get("retry-after")
+                            .and_then(|value| value.trim().parse().ok())
+                            .map(Duration::from_secs),
+                    },
+                }
+            }
+            _ => Error::Storage {
+                message,
+                retryable: false,
+                retry_after: None,
+            },
+        }
+    }
+
+    /// The category as sent to the frontend, e.g. "auth".
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::Config(_) => "config",
+            Error::Auth(_) => "auth",
+            Error::Network(_) => "network",
+            Error::NotFound(_) => "not_found",
+            Error::Conflict(_) => "conflict",
+            Error::NeedsConfirmation(_) => "needs_confirmation",
+            Error::Integrity(_) => "integrity",
+            Error::Storage { .. } => "storage",
+            Error::LocalIo(_) => "local_io",
+            Error::Incomplete { .. } => "incomplete",
+            Error::Cancelled => "cancelled",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// Whether trying the same operation again later may succeed.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            Error::Network(_) => true,
+            Error::Storage { retryable, .. } => *retryable,
+            Error::LocalIo(err) => matches!(err.kind(), io::ErrorKind::Interrupted | io::ErrorKind::TimedOut),
+            Error::Incomplete { failed, .. } => failed.iter().any(|(_, err)| err.is_retryable()),
+            _ => false,
+        }
+    }
+
+    /// How long the storage asked to wait before trying again.
+    pub fn retry_after(&self) -> Option<Duration> {
+        match self {
+            Error::Storage { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Whether the error dooms the rest of a transfer too, so there is no
+    /// point going on with the other files.
+    pub fn stops_transfer(&self) -> bool {
+        matches!(self, Error::Cancelled | Error::Auth(_) | Error::Config(_))
+    }
+
+    /// What the user can do about the error, if there is anything.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            Error::Config(_) => Some("Check the storage settings (bucket, endpoint, region) and the sync rules."),
+            Error::Auth(_) => Some("Check the key ID and application key in the storage settings; the key may be revoked or lack access to the bucket."),
+            Error::Network(_) => Some("Check the internet connection and the storage endpoint, then try again."),
+            Error::NotFound(_) => Some("Check that the bucket exists and that Vintage Story's data folder is found; VS_DATA_DIR overrides it."),
+            Error::Conflict(_) => Some("Wait until the world is checked in or closed in Vintage Story, then try again."),
+            Error::NeedsConfirmation(_) => Some("Review the plan with its warnings and conflicts, and confirm to go ahead."),
+            Error::Integrity(_) => Some("The data may be corrupt; try again, or restore a snapshot."),
+            Error::Storage { retryable: true, .. } => Some("The storage is busy or failing; try again in a moment."),
+            Error::Incomplete { .. } => Some("The other files were transferred; try again to retry the failed ones."),
+            Error::LocalIo(err) => match err.kind() {
+                io::ErrorKind::StorageFull => Some("The disk is full; free some space and try again."),
+                io::ErrorKind::PermissionDenied => Some("Check the permissions of the Vintage Story data folder."),
+                _ => None,
+            },
+            Error::Storage { retryable: false, .. } | Error::Cancelled | Error::Other(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Cancelled => f.write_str("cancelled"),
+            Error::Config(message)
+            | Error::Auth(message)
+            | Error::Network(message)
+            | Error::NotFound(message)
+            | Error::Conflict(message)
+            | Error::NeedsConfirmation(message)
+            | Error::Integrity(message)
+            | Error::Storage { message, .. }
+            | Error::Other(message) => f.write_str(message),
+            Error::LocalIo(err) => write!(f, "{}", err),
+            Error::Incomplete { failed, total } => {
+                write!(f, "{} of {} files failed:", failed.len(), total)?;
+                for (key, err) in failed {
+                    write!(f, "\n{}: {}", key, err)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::LocalIo(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(err: io::Error) -> Error {
+        Error::LocalIo(err)
+    }
+}
+
+/// What the frontend gets of an `Error`: tagged with `kind` so it can
+/// branch on the category, with the message and hint ready to show.
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorReport {
+    pub kind: &'static str,
+    pub message: String,
+    pub retryable: bool,
+    pub hint: Option<&'static str>,
+}
+
+impl From<&Error> for ErrorReport {
+    fn from(err: &Error) -> ErrorReport {
+        ErrorReport {
+            kind: err.kind(),
+            message: err.to_string(),
+            retryable: err.is_retryable(),
+            hint: err.hint(),
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ErrorReport::from(self).serialize(serializer)
+    }
+}
@@ -0,0 +1,218 @@
+use rusqlite::{Connection, DatabaseName, OpenFlags};
+use serde::Serialize;
+use std::ffi::OsStr;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+use tempfile::TempPath;
+
+use crate::error::Error;
+use crate::helper_functions::{resolve_program_data_dir, resolve_save_dir};
+
+/// Executables of the game client and the dedicated server.
+const GAME_EXECUTABLES: [&str; 2] = ["Vintagestory", "VintagestoryServer"];
+
+/// What the game is doing right now, as shown in the UIs.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GameStatus {
+    pub running: bool,
+    /// File names of worlds in the Saves folder the game has open.
+    pub open_worlds: Vec<String>,
+}
+
+impl GameStatus {
+    pub fn summary(&self) -> String {
+        match (self.running, self.open_worlds.as_slice()) {
+            (false, _) => "Vintage Story is not running".to_string(),
+            (true, []) => "Vintage Story is running, no world open".to_string(),
+            (true, worlds) => format!("Vintage Story is running with {} open", worlds.join(", ")),
+        }
+    }
+}
+
+/// A snapshot of the running game processes.
+pub struct GameProcesses {
+    pids: Vec<u32>,
+}
+
+impl GameProcesses {
+    pub fn scan() -> GameProcesses {
+        let mut system = System::new();
+        system.refresh_processes_specifics(
+            ProcessesToUpdate::All,
+            true,
+            ProcessRefreshKind::nothing()
+                .with_exe(UpdateKind::OnlyIfNotSet)
+                .with_cmd(UpdateKind::OnlyIfNotSet),
+        );
+
+        let pids = system
+            .processes()
+            .iter()
+            .filter(|(_, process)| {
+                is_game_executable(process.name())
+                    || process.exe().and_then(Path::file_name).is_some_and(is_game_executable)
+                    // Launched through `dotnet Vintagestory.dll`.
+                    || process
+                        .cmd()
+                        .iter()
+                        .filter_map(|arg| Path::new(arg).file_name())
+                        .any(is_game_executable)
+            })
+            .map(|(pid, _)| pid.as_u32())
+            .collect();
+        GameProcesses { pids }
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.pids.is_empty()
+    }
+
+    /// Whether the game currently holds `path` open. Always false while the
+    /// game is not running.
+    pub fn holds_open(&self, path: &Path) -> bool {
+        self.is_running() && path.is_file() && self.file_in_use(path)
+    }
+
+    /// Linux: look for the file among the game's open descriptors.
+    #[cfg(target_os = "linux")]
+    fn file_in_use(&self, path: &Path) -> bool {
+        let Ok(target) = path.canonicalize() else {
+            return false;
+        };
+        self.pids.iter().any(|pid| {
+            fs::read_dir(format!("/proc/{}/fd", pid))
+                .map(|entries| {
+                    entries
+                        .filter_map(Result::ok)
+                        .any(|fd| fs::read_link(fd.path()).is_ok_and(|linked| linked == target))
+                })
+                .unwrap_or(false)
+        })
+    }
+
+    /// Windows: SQLite keeps the file open with sharing enabled, so an
+    /// exclusive open fails while the game has the world loaded.
+    #[cfg(target_os = "windows")]
+    fn file_in_use(&self, path: &Path) -> bool {
+        use std::os::windows::fs::OpenOptionsExt;
+        const ERROR_SHARING_VIOLATION: i32 = 32;
+        fs::OpenOptions::new()
+            .read(true)
+            .share_mode(0)
+            .open(path)
+            .is_err_and(|err| err.raw_os_error() == Some(ERROR_SHARING_VIOLATION))
+    }
+
+    /// Elsewhere: SQLite leaves its side files next to a world while it is
+    /// open.
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    fn file_in_use(&self, path: &Path) -> bool {
+        ["-journal", "-wal"].iter().any(|suffix| {
+            let mut side_file = path.as_os_str().to_os_string();
+            side_file.push(suffix);
+            Path::new(&side_file).exists()
+        })
+    }
+}
+
+fn is_game_executable(name: &OsStr) -> bool {
+    let name = name.to_string_lossy();
+    let stem = name
+        .strip_suffix(".exe")
+        .or_else(|| name.strip_suffix(".dll"))
+        .unwrap_or(&name);
+    GAME_EXECUTABLES.contains(&stem)
+}
+
+/// Checks whether the game runs and which worlds in the Saves folder it has
+/// open.
+pub fn game_status() -> Result<GameStatus, Error> {
+    let processes = GameProcesses::scan();
+    if !processes.is_running() {
+        return Ok(GameStatus::default());
+    }
+
+    let mut open_worlds: Vec<String> = world_files(&resolve_save_dir()?)
+        .into_iter()
+        .filter(|world| processes.holds_open(world))
+        .filter_map(|world| world.file_name().map(|name| name.to_string_lossy().to_string()))
+        .collect();
+    open_worlds.sort();
+
+    Ok(GameStatus {
+        running: true,
+        open_worlds,
+    })
+}
+
+fn world_files(save_dir: &Path) -> Vec<PathBuf> {
+    fs::read_dir(save_dir)
+        .map(|entries| {
+            entries
+                .filter_map(Result::ok)
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().is_some_and(|ext| ext == "vcdbs"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Copies a world through SQLite's backup API so the copy is consistent even
+/// while the game keeps writing to it. Every call gets its own file in the
+/// snapshots folder, removed when the returned path is dropped.
+pub fn snapshot_world(world: &Path) -> Result<TempPath, Error> {
+    let snapshot_dir = resolve_program_data_dir()?.join("snapshots");
+    fs::create_dir_all(&snapshot_dir)?;
+    let file_stem = world
+        .file_stem()
+        .ok_or_else(|| Error::other(format!("not a world file: {}", world.display())))?;
+    let snapshot = tempfile::Builder::new()
+        .prefix(file_stem)
+        .suffix(".vcdbs")
+        .tempfile_in(&snapshot_dir)?
+        .into_temp_path();
+
+    Connection::open_with_flags(
+        world,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .and_then(|connection| connection.backup(DatabaseName::Main, &snapshot, None))
+    .map_err(|err| Error::other(format!("failed to snapshot {}: {}", world.display(), err)))?;
+    Ok(snapshot)
+}
+
+/// Starts the game client from its install directory without waiting for
+/// it to exit.
+pub fn launch_game() -> Result<(), Error> {
+    #[cfg(target_os = "macos")]
+    let mut command = {
+        let mut command = Command::new("open");
+        command.arg("-a").arg("Vintage Story");
+        command
+    };
+
+    #[cfg(not(target_os = "macos"))]
+    let mut command = {
+        let install_dir = crate::helper_functions::resolve_install_dir().ok_or_else(|| {
+            Error::NotFound("could not find the Vintage Story install; set VS_INSTALL_DIR".to_string())
+        })?;
+        let executable = install_dir.join(if cfg!(windows) { "Vintagestory.exe" } else { "Vintagestory" });
+        let mut command = if executable.is_file() {
+            Command::new(executable)
+        } else {
+            // Older Linux builds only ship the managed assembly.
+            let mut command = Command::new("dotnet");
+            command.arg(install_dir.join("Vintagestory.dll"));
+            command
+        };
+        command.current_dir(install_dir);
+        command
+    };
+
+    command
+        .spawn()
+        .map_err(|e| Error::other(format!("failed to launch Vintage Story: {}", e)))?;
+    Ok(())
+}
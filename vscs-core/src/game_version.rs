@@ -0,0 +1,224 @@
+use protobuf::rt::WireType;
+use protobuf::CodedInputStream;
+use rusqlite::{Connection, OpenFlags};
+use std::cmp::Ordering;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+
+use crate::helper_functions::resolve_install_dir;
+
+/// A game version such as `1.20.4` or `1.21.0-rc.2`. Versions compare the way
+/// the game orders its releases: dev < pre < rc < stable for the same number.
+#[derive(Debug, Clone)]
+pub struct GameVersion {
+    numbers: [u32; 3],
+    stage: Stage,
+    stage_number: u32,
+    text: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Stage {
+    Dev,
+    Pre,
+    Rc,
+    Stable,
+}
+
+impl GameVersion {
+    pub fn parse(text: &str) -> Option<GameVersion> {
+        // .NET appends the source revision to product versions (`1.20.4+abc`).
+        let text = text.trim().split('+').next().unwrap_or_default();
+        let (numbers_part, suffix) = match text.split_once('-') {
+            Some((numbers, suffix)) => (numbers, Some(suffix)),
+            None => (text, None),
+        };
+
+        let parts: Vec<u32> = numbers_part
+            .split('.')
+            .map(|part| part.parse().ok())
+            .collect::<Option<_>>()?;
+        if !(2..=4).contains(&parts.len()) {
+            return None;
+        }
+        let mut numbers = [0; 3];
+        for (slot, part) in numbers.iter_mut().zip(&parts) {
+            *slot = *part;
+        }
+
+        let (stage, stage_number) = match suffix {
+            None => (Stage::Stable, 0),
+            Some(suffix) => {
+                let (name, number) = suffix.split_once('.').unwrap_or((suffix, "0"));
+                let stage = match name.to_ascii_lowercase().as_str() {
+                    "dev" => Stage::Dev,
+                    "pre" => Stage::Pre,
+                    "rc" => Stage::Rc,
+                    _ => return None,
+                };
+                (stage, number.parse().ok()?)
+            }
+        };
+
+        Some(GameVersion {
+            numbers,
+            stage,
+            stage_number,
+            text: text.to_string(),
+        })
+    }
+}
+
+/// Equal when the numbers and stage are, however they were written
+/// (`1.21` and `1.21.0`), to agree with `Ord`.
+impl PartialEq for GameVersion {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for GameVersion {}
+
+impl Ord for GameVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.numbers, self.stage, self.stage_number).cmp(&(other.numbers, other.stage, other.stage_number))
+    }
+}
+
+impl PartialOrd for GameVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl fmt::Display for GameVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Game version that last saved the world in `path`, read from the
+/// savegame record in its `gamedata` table. `None` when the file is not a
+/// readable savegame.
+pub fn savegame_version(path: &Path) -> Option<String> {
+    newest_version_string(&savegame_record(path)?).map(|version| version.to_string())
+}
+
+/// The savegame record of the world in `path`: a protobuf message in its
+/// `gamedata` table.
+pub(crate) fn savegame_record(path: &Path) -> Option<Vec<u8>> {
+    let connection = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .ok()?;
+    connection
+        .query_row("SELECT data FROM gamedata LIMIT 1", [], |row| row.get(0))
+        .ok()
+}
+
+/// The length-delimited top level fields of the protobuf message `data`
+/// (strings, bytes and embedded messages) of at most `max_len` bytes. The
+/// chunky blobs are skipped without decoding them.
+pub(crate) fn short_fields(data: &[u8], max_len: usize) -> Vec<Vec<u8>> {
+    let mut input = CodedInputStream::from_bytes(data);
+    let mut fields = Vec::new();
+
+    while let Ok(Some(tag)) = input.read_raw_tag_or_eof() {
+        let Some(wire_type) = WireType::new(tag & 7) else {
+            break;
+        };
+        if wire_type != WireType::LengthDelimited {
+            if input.skip_field(wire_type).is_err() {
+                break;
+            }
+            continue;
+        }
+        let Ok(bytes) = input.read_bytes() else {
+            break;
+        };
+        if bytes.len() <= max_len {
+            fields.push(bytes);
+        }
+    }
+
+    fields
+}
+
+/// The savegame is a protobuf message holding both the version that created
+/// the world and the one that last saved it. Rather than depending on field
+/// numbers that change between game releases, take the newest version-like
+/// string among the top level fields: a world is never saved by a version
+/// older than the one that created it.
+fn newest_version_string(data: &[u8]) -> Option<GameVersion> {
+    short_fields(data, 32)
+        .iter()
+        .filter_map(|bytes| std::str::from_utf8(bytes).ok().and_then(GameVersion::parse))
+        .max()
+}
+
+/// Version of the locally installed game, read from the version resource of
+/// `VintagestoryAPI.dll` in the install directory. `VS_GAME_VERSION`
+/// overrides detection.
+pub fn installed_game_version() -> Option<GameVersion> {
+    if let Ok(overridden) = std::env::var("VS_GAME_VERSION") {
+        return GameVersion::parse(&overridden);
+    }
+
+    let dll = fs::read(resolve_install_dir()?.join("VintagestoryAPI.dll")).ok()?;
+    ["ProductVersion", "FileVersion"]
+        .iter()
+        .find_map(|name| version_resource_value(&dll, name).and_then(|value| GameVersion::parse(&value)))
+}
+
+/// Looks up a string from a Windows version resource. Keys and values are
+/// stored as UTF-16LE, the value following its key after NUL padding.
+fn version_resource_value(binary: &[u8], name: &str) -> Option<String> {
+    let key: Vec<u8> = name.encode_utf16().flat_map(u16::to_le_bytes).collect();
+    let start = binary.windows(key.len()).position(|window| window == key.as_slice())? + key.len();
+
+    let units: Vec<u16> = binary[start..]
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .skip_while(|unit| *unit == 0)
+        .take_while(|unit| *unit != 0)
+        .take(64)
+        .collect();
+    String::from_utf16(&units).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> GameVersion {
+        GameVersion::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_release_and_prerelease_versions() {
+        assert_eq!(version("1.20.4").to_string(), "1.20.4");
+        assert_eq!(version(" 1.20.4+a1b2c3 ").to_string(), "1.20.4");
+        assert_eq!(version("1.21"), version("1.21.0"));
+        assert_ne!(version("1.21.0-rc.1"), version("1.21.0"));
+        assert!(GameVersion::parse("1.21.0-beta.1").is_none());
+        assert!(GameVersion::parse("1").is_none());
+        assert!(GameVersion::parse("v1.20").is_none());
+    }
+
+    #[test]
+    fn orders_stages_before_the_release() {
+        let mut versions: Vec<GameVersion> =
+            ["1.21.0", "1.21.0-rc.2", "1.20.11", "1.21.0-pre.1", "1.21.0-rc.10", "1.21.0-dev.3"]
+                .into_iter()
+                .map(version)
+                .collect();
+        versions.sort();
+        let sorted: Vec<String> = versions.iter().map(GameVersion::to_string).collect();
+        assert_eq!(
+            sorted,
+            ["1.20.11", "1.21.0-dev.3", "1.21.0-pre.1", "1.21.0-rc.2", "1.21.0-rc.10", "1.21.0"]
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::game_process::{launch_game, GameProcesses};
+use crate::manifest_info::AutoSyncSettings;
+use crate::progress::{ProgressCallback, TransferProgress};
+use crate::scheduler::ScheduledAction;
+use crate::upload_core::{download_save, upload_save, DownloadOptions, UploadOptions};
+
+/// How often the process table is checked for the game.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// Granularity at which the watcher thread notices it should stop.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// What the game watcher did, for the UIs to show.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum WatcherEvent {
+    GameStarted,
+    GameStopped { upload_in_secs: u64 },
+    /// The save watcher saw these worlds settle after being written.
+    WorldsChanged { worlds: Vec<String> },
+    /// A schedule of `folder_bucket` came due.
+    ScheduledRun {
+        folder_bucket: String,
+        action: ScheduledAction,
+    },
+    UploadStarted,
+    UploadProgress(TransferProgress),
+    UploadFinished,
+    UploadFailed { error: String },
+}
+
+/// Background thread that uploads the current folder bucket a while after
+/// Vintage Story exits. Stops when dropped, letting an upload that already
+/// started run to completion.
+pub struct GameWatcher {
+    stop: Arc<AtomicBool>,
+    folder_bucket: Arc<Mutex<String>>,
+}
+
+impl GameWatcher {
+    pub fn start(folder_bucket: String, upload_delay: Duration, events: Sender<WatcherEvent>) -> GameWatcher {
+        let stop = Arc::new(AtomicBool::new(false));
+        let folder_bucket = Arc::new(Mutex::new(folder_bucket));
+        {
+            let stop = stop.clone();
+            let folder_bucket = folder_bucket.clone();
+            thread::spawn(move || watch(&stop, &folder_bucket, upload_delay, &events));
+        }
+        GameWatcher { stop, folder_bucket }
+    }
+
+    /// Changes the folder bucket uploaded on the next game exit.
+    pub fn set_folder_bucket(&self, folder_bucket: &str) {
+        if let Ok(mut current) = self.folder_bucket.lock() {
+            *current = folder_bucket.to_string();
+        }
+    }
+}
+
+impl Drop for GameWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+fn watch(stop: &AtomicBool, folder_bucket: &Mutex<String>, upload_delay: Duration, events: &Sender<WatcherEvent>) {
+    let mut was_running = GameProcesses::scan().is_running();
+    let mut upload_due: Option<Instant> = None;
+    let mut next_poll = Instant::now();
+
+    while !stop.load(Ordering::Relaxed) {
+        if Instant::now() < next_poll {
+            thread::sleep(STOP_CHECK_INTERVAL);
+            continue;
+        }
+        next_poll = Instant::now() + POLL_INTERVAL;
+
+        let running = GameProcesses::scan().is_running();
+        if running && !was_running {
+            // Back in the game before the upload ran; wait for the next exit.
+            upload_due = None;
+            let _ = events.send(WatcherEvent::GameStarted);
+        } else if !running && was_running {
+            upload_due = Some(Instant::now() + upload_delay);
+            let _ = events.send(WatcherEvent::GameStopped {
+                upload_in_secs: upload_delay.as_secs(),
+            });
+        }
+        was_running = running;
+
+        if upload_due.is_some_and(|due| Instant::now() >= due) {
+            upload_due = None;
+            let folder = folder_bucket.lock().map(|folder| folder.clone()).unwrap_or_default();
+            run_watched_upload(events, &format!("{} after Vintage Story closed", folder), |progress| {
+                upload_save(folder.clone(), Some(progress), UploadOptions::default())
+            });
+        }
+    }
+}
+
+/// Runs an upload started by a watcher, reporting it through `events` and a
+/// desktop notification. `what` describes the upload for the notification.
+pub fn run_watched_upload<F>(events: &Sender<WatcherEvent>, what: &str, upload: F)
+where
+    F: FnOnce(ProgressCallback<'_>) -> Result<(), Error>,
+{
+    let _ = events.send(WatcherEvent::UploadStarted);
+
+    let result = upload(&|progress| {
+        let _ = events.send(WatcherEvent::UploadProgress(progress));
+    });
+    match result {
+        Ok(()) => {
+            notify("Saves uploaded", &format!("Uploaded {}.", what));
+            let _ = events.send(WatcherEvent::UploadFinished);
+        }
+        Err(err) => {
+            notify("Upload failed", &format!("Could not upload {}: {}", what, err));
+            let _ = events.send(WatcherEvent::UploadFailed { error: err.to_string() });
+        }
+    }
+}
+
+/// Starts Vintage Story, first downloading the latest saves of
+/// `folder_bucket` when `settings` asks for it. A failed download keeps the
+/// game from starting so it never opens outdated worlds.
+pub fn launch_with_sync(folder_bucket: String, settings: &AutoSyncSettings) -> Result<(), Error> {
+    if settings.download_before_launch {
+        if let Err(err) = download_save(folder_bucket.clone(), None, DownloadOptions::default()) {
+            notify("Download failed", &format!("Could not download {}: {}", folder_bucket, err));
+            return Err(err);
+        }
+        notify("Saves downloaded", &format!("Downloaded the latest {} before launch.", folder_bucket));
+    }
+    launch_game()
+}
+
+/// Shows a desktop notification. Failing to show one is not worth an error.
+fn notify(summary: &str, body: &str) {
+    let _ = notify_rust::Notification::new()
+        .appname("Vintage Story Cloud Save")
+        .summary(summary)
+        .body(body)
+        .show();
+}
@@ -0,0 +1,242 @@
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::error::Error;
+use crate::profile::{active_profile, DataProfile};
+
+/// SHA-256 of the file at `path`, in lowercase hex.
+pub fn file_sha256(path: &Path) -> io::Result<String> {
+    let mut hasher = Sha256::new();
+    io::copy(&mut fs::File::open(path)?, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The value of the environment variable `name`, which locates a directory.
+fn env_dir(name: &str) -> Result<String, Error> {
+    env::var(name).map_err(|_| Error::NotFound(format!("{} is not set", name)))
+}
+
+pub fn resolve_save_dir() -> Result<PathBuf, Error> {
+    if let Ok(overridden) = env::var("VS_SAVE_DIR") {
+        return Ok(PathBuf::from(overridden));
+    }
+
+    let saves = resolve_data_root()?.join("Saves");
+
+    // create the Saves folder if it doesn't exist
+    if !saves.exists() {
+        fs::create_dir_all(&saves)?;
+    }
+
+    Ok(saves)
+}
+
+/// The game's VintagestoryData directory (Saves, Mods, ModConfig, settings).
+/// With the server profile active this is the server's `--dataPath`.
+pub fn resolve_data_root() -> Result<PathBuf, Error> {
+    if let Ok(overridden) = env::var("VS_DATA_DIR") {
+        return Ok(PathBuf::from(overridden));
+    }
+    if let DataProfile::Server(server) = active_profile() {
+        return Ok(PathBuf::from(server.data_path));
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let appdata = env_dir("APPDATA")?;
+        return Ok(Path::new(&appdata).join("VintagestoryData"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let home = env_dir("HOME")?;
+        return Ok(Path::new(&home)
+            .join("Library")
+            .join("Application Support")
+            .join("VintagestoryData"));
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        if let Some(xdg_config) = env::var("XDG_CONFIG_HOME").ok().filter(|dir| !dir.is_empty()) {
+            return Ok(Path::new(&xdg_config).join("VintagestoryData"));
+        }
+        let home = env::var("HOME").map_err(|_| {
+            Error::NotFound("could not locate VintagestoryData; set VS_DATA_DIR".to_string())
+        })?;
+        Ok(Path::new(&home).join(".config").join("VintagestoryData"))
+    }
+}
+
+/// Directory holding this program's own state (manifest, settings). Kept
+/// outside of VintagestoryData so the game never sees our files.
+pub fn resolve_program_data_dir() -> Result<PathBuf, Error> {
+    let dir = if let Ok(overridden) = env::var("VSCS_DATA_DIR") {
+        PathBuf::from(overridden)
+    } else {
+        default_program_data_dir()?
+    };
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+    Ok(dir)
+}
+
+fn default_program_data_dir() -> Result<PathBuf, Error> {
+    #[cfg(target_os = "windows")]
+    {
+        let appdata = env_dir("APPDATA")?;
+        return Ok(Path::new(&appdata).join("VintageStoryCloudSave"));
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let home = env_dir("HOME")?;
+        return Ok(Path::new(&home)
+            .join("Library")
+            .join("Application Support")
+            .join("VintageStoryCloudSave"));
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        if let Some(xdg_data) = env::var("XDG_DATA_HOME").ok().filter(|dir| !dir.is_empty()) {
+            return Ok(Path::new(&xdg_data).join("vintage-story-cloud-save"));
+        }
+        let home = env_dir("HOME")?;
+        Ok(Path::new(&home)
+            .join(".local")
+            .join("share")
+            .join("vintage-story-cloud-save"))
+    }
+}
+
+/// Directory holding the user-editable configuration (storage settings,
+/// folder buckets). Separate from the program data so it can be backed up or
+/// shared without the manifest.
+pub fn resolve_config_dir() -> Result<PathBuf, Error> {
+    let dir = if let Ok(overridden) = env::var("VSCS_CONFIG_DIR") {
+        PathBuf::from(overridden)
+    } else {
+        default_config_dir()?
+    };
+
+    if !dir.exists() {
+        fs::create_dir_all(&dir)?;
+    }
+    Ok(dir)
+}
+
+fn default_config_dir() -> Result<PathBuf, Error> {
+    #[cfg(any(target_os = "windows", target_os = "macos"))]
+    {
+        // These platforms keep settings and data in the same place.
+        default_program_data_dir()
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        if let Some(xdg_config) = env::var("XDG_CONFIG_HOME").ok().filter(|dir| !dir.is_empty()) {
+            return Ok(Path::new(&xdg_config).join("vintage-story-cloud-save"));
+        }
+        let home = env_dir("HOME")?;
+        Ok(Path::new(&home).join(".config").join("vintage-story-cloud-save"))
+    }
+}
+
+/// The game's install directory, where `VintagestoryAPI.dll` lives. Unlike
+/// the data root it can be anywhere, so the usual locations are probed and
+/// `None` is returned when none of them holds the game.
+pub fn resolve_install_dir() -> Option<PathBuf> {
+    if let Ok(overridden) = env::var("VS_INSTALL_DIR") {
+        return Some(PathBuf::from(overridden));
+    }
+
+    default_install_dirs()
+        .into_iter()
+        .find(|dir| dir.join("VintagestoryAPI.dll").is_file())
+}
+
+fn default_install_dirs() -> Vec<PathBuf> {
+    let mut dirs = Vec::new();
+
+    #[cfg(target_os = "windows")]
+    {
+        if let Ok(appdata) = env::var("APPDATA") {
+            dirs.push(Path::new(&appdata).join("Vintagestory"));
+        }
+        if let Ok(program_files) = env::var("ProgramFiles") {
+            dirs.push(Path::new(&program_files).join("Vintagestory"));
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let app = Path::new("/Applications").join("Vintage Story.app").join("Contents");
+        dirs.push(app.join("Resources").join("vintagestory"));
+        dirs.push(app.join("MacOS"));
+    }
+
+    #[cfg(not(any(target_os = "windows", target_os = "macos")))]
+    {
+        if let Ok(home) = env::var("HOME") {
+            dirs.push(Path::new(&home).join(".local").join("share").join("vintagestory"));
+            dirs.push(Path::new(&home).join("ApplicationData").join("vintagestory"));
+        }
+        dirs.push(PathBuf::from("/opt/vintagestory"));
+        dirs.push(PathBuf::from("/usr/share/vintagestory"));
+    }
+
+    dirs
+}
+
+/// Moves `from` to `to`, falling back to copy + delete when the two paths are
+/// on different filesystems.
+pub fn move_file(from: &Path, to: &Path) -> Result<(), Error> {
+    if let Some(parent) = to.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    fs::copy(from, to)?;
+    fs::remove_file(from)?;
+    Ok(())
+}
+
+/// Runs a user-configured command through the platform shell with the
+/// extra environment `envs`, failing when it exits unsuccessfully.
+pub fn run_shell_command(command: &str, envs: &[(&str, String)]) -> Result<(), Error> {
+    let mut shell = if cfg!(windows) {
+        let mut shell = Command::new("cmd");
+        shell.arg("/C");
+        shell
+    } else {
+        let mut shell = Command::new("sh");
+        shell.arg("-c");
+        shell
+    };
+    let status = shell
+        .arg(command)
+        .envs(envs.iter().map(|(key, value)| (key, value)))
+        .status()
+        .map_err(|e| Error::other(format!("failed to run `{}`: {}", command, e)))?;
+    if !status.success() {
+        return Err(Error::other(format!("`{}` exited with {}", command, status)));
+    }
+    Ok(())
+}
+
+/// Path of `file` relative to `root`, with `/` separators on every platform.
+/// Used both as the manifest key and as the remote object key suffix.
+pub fn file_key(root: &Path, file: &Path) -> String {
+    file.strip_prefix(root)
+        .unwrap_or(file)
+        .to_string_lossy()
+        .replace('\\', "/")
+}
@@ -0,0 +1,165 @@
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::helper_functions::run_shell_command;
+use crate::manifest_info::{get_manifest_info, FileInfo};
+
+/// Shell commands run around uploads and downloads, e.g. to stop a server or
+/// post to a chat. Each hook gets these environment variables:
+/// `VSCS_HOOK` (the stage), `VSCS_FOLDER_BUCKET`, `VSCS_WORLDS` (the worlds
+/// being synced, one per line) and, for post hooks, `VSCS_RESULT`
+/// (`success` or `failure`) and `VSCS_ERROR`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookSettings {
+    /// Exiting non-zero aborts the upload.
+    pub pre_upload: Option<String>,
+    pub post_upload: Option<String>,
+    /// Exiting non-zero aborts the download.
+    pub pre_download: Option<String>,
+    pub post_download: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HookStage {
+    PreUpload,
+    PostUpload,
+    PreDownload,
+    PostDownload,
+}
+
+impl HookStage {
+    pub fn name(self) -> &'static str {
+        match self {
+            HookStage::PreUpload => "pre_upload",
+            HookStage::PostUpload => "post_upload",
+            HookStage::PreDownload => "pre_download",
+            HookStage::PostDownload => "post_download",
+        }
+    }
+
+    fn command(self, hooks: &HookSettings) -> Option<&str> {
+        let command = match self {
+            HookStage::PreUpload => &hooks.pre_upload,
+            HookStage::PostUpload => &hooks.post_upload,
+            HookStage::PreDownload => &hooks.pre_download,
+            HookStage::PostDownload => &hooks.post_download,
+        };
+        command.as_deref().map(str::trim).filter(|command| !command.is_empty())
+    }
+}
+
+/// Runs the pre hook of `stage`, if one is configured. An error means the
+/// hook failed and the sync must not go ahead.
+pub fn run_pre_hook<'a>(
+    stage: HookStage,
+    folder_bucket: &str,
+    keys: impl IntoIterator<Item = &'a str>,
+) -> Result<(), Error> {
+    run_hook(stage, folder_bucket, keys, None)
+        .map_err(|err| Error::other(format!("{} hook failed, sync aborted: {}", stage.name(), err)))
+}
+
+/// Runs the post hook of `stage` with the outcome of the sync. A failing
+/// post hook is only logged; the sync already happened.
+pub fn run_post_hook<'a>(
+    stage: HookStage,
+    folder_bucket: &str,
+    keys: impl IntoIterator<Item = &'a str>,
+    result: &Result<(), Error>,
+) {
+    if let Err(err) = run_hook(stage, folder_bucket, keys, Some(result)) {
+        println!("{} hook failed: {}", stage.name(), err);
+    }
+}
+
+fn run_hook<'a>(
+    stage: HookStage,
+    folder_bucket: &str,
+    keys: impl IntoIterator<Item = &'a str>,
+    result: Option<&Result<(), Error>>,
+) -> Result<(), Error> {
+    let hooks = get_manifest_info()?.hooks;
+    let Some(command) = stage.command(&hooks) else {
+        return Ok(());
+    };
+
+    println!("Running {} hook: {}", stage.name(), command);
+    run_shell_command(command, &hook_env(stage, folder_bucket, keys, result))
+}
+
+/// The environment variables a hook of `stage` gets, as described on
+/// [`HookSettings`].
+fn hook_env<'a>(
+    stage: HookStage,
+    folder_bucket: &str,
+    keys: impl IntoIterator<Item = &'a str>,
+    result: Option<&Result<(), Error>>,
+) -> Vec<(&'static str, String)> {
+    let worlds: Vec<&str> = keys.into_iter().filter(|key| FileInfo::is_world_key(key)).collect();
+    let mut envs = vec![
+        ("VSCS_HOOK", stage.name().to_string()),
+        ("VSCS_FOLDER_BUCKET", folder_bucket.to_string()),
+        ("VSCS_WORLDS", worlds.join("\n")),
+    ];
+    if let Some(result) = result {
+        let (outcome, error) = match result {
+            Ok(()) => ("success", String::new()),
+            Err(err) => ("failure", err.to_string()),
+        };
+        envs.push(("VSCS_RESULT", outcome.to_string()));
+        envs.push(("VSCS_ERROR", error));
+    }
+    envs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value<'a>(envs: &'a [(&str, String)], name: &str) -> Option<&'a str> {
+        envs.iter().find(|(key, _)| *key == name).map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn pre_hooks_get_the_worlds_being_synced() {
+        let envs = hook_env(HookStage::PreUpload, "server", ["a.vcdbs", "_mods/x.zip", "b.vcdbs"], None);
+        assert_eq!(value(&envs, "VSCS_HOOK"), Some("pre_upload"));
+        assert_eq!(value(&envs, "VSCS_FOLDER_BUCKET"), Some("server"));
+        assert_eq!(value(&envs, "VSCS_WORLDS"), Some("a.vcdbs\nb.vcdbs"));
+        assert_eq!(value(&envs, "VSCS_RESULT"), None);
+    }
+
+    #[test]
+    fn post_hooks_get_the_outcome() {
+        let envs = hook_env(HookStage::PostDownload, "server", [], Some(&Ok(())));
+        assert_eq!(value(&envs, "VSCS_RESULT"), Some("success"));
+        assert_eq!(value(&envs, "VSCS_ERROR"), Some(""));
+
+        let error = Error::Network("timed out".to_string());
+        let message = error.to_string();
+        let envs = hook_env(HookStage::PostDownload, "server", [], Some(&Err(error)));
+        assert_eq!(value(&envs, "VSCS_RESULT"), Some("failure"));
+        assert_eq!(value(&envs, "VSCS_ERROR"), Some(message.as_str()));
+    }
+
+    #[test]
+    fn blank_commands_are_no_hook() {
+        let hooks = HookSettings {
+            pre_upload: Some("  ".to_string()),
+            post_upload: Some(" notify-send done ".to_string()),
+            ..HookSettings::default()
+        };
+        assert_eq!(HookStage::PreUpload.command(&hooks), None);
+        assert_eq!(HookStage::PostUpload.command(&hooks), Some("notify-send done"));
+        assert_eq!(HookStage::PreDownload.command(&hooks), None);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn a_failing_command_is_an_error() {
+        let envs = hook_env(HookStage::PreUpload, "server", [], None);
+        assert!(run_shell_command(r#"test "$VSCS_HOOK" = pre_upload"#, &envs).is_ok());
+        assert!(run_shell_command("exit 3", &envs).is_err());
+    }
+}
@@ -0,0 +1,31 @@
+//! Syncing Vintage Story saves with S3-compatible storage, shared by the
+//! egui and the Tauri app. The apps only add their UI on top: everything
+//! that reads the game's data, talks to the bucket or runs in the
+//! background lives here.
+
+pub mod cancel;
+pub mod config;
+pub mod credentials;
+pub mod device;
+pub mod error;
+pub mod game_process;
+pub mod game_version;
+pub mod game_watcher;
+pub mod helper_functions;
+pub mod hooks;
+pub mod manifest_info;
+pub mod mods;
+pub mod profile;
+pub mod progress;
+pub mod remote_manifest;
+pub mod retry;
+pub mod save_watcher;
+pub mod scheduler;
+pub mod sync_filter;
+pub mod sync_plan;
+pub mod sync_scope;
+pub mod upload_core;
+pub mod verify;
+pub mod world_lock;
+
+pub use error::Error;
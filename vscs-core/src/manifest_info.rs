@@ -0,0 +1,517 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
+use crate::device::{DeviceIdentity, UploadRecord};
+use crate::error::Error;
+use crate::game_version::savegame_version;
+use crate::helper_functions::{move_file, resolve_program_data_dir, resolve_save_dir};
+use crate::hooks::HookSettings;
+use crate::mods::{savegame_mods, ModRequirement};
+use crate::profile::DataProfile;
+use crate::scheduler::Schedule;
+use crate::sync_filter::SyncFilter;
+use crate::sync_scope::SyncScope;
+use serde::{Deserialize, Serialize};
+
+/// Name the manifest used to have when it lived inside the Saves folder.
+pub const LEGACY_MANIFEST_FILE_NAME: &str = ".cloud_save_manifest.json";
+const MANIFEST_FILE_NAME: &str = "cloud_save_manifest.json";
+
+/// Held while the program data is read, changed and written back.
+static PROGRAM_DATA_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct FileInfo {
+    pub world_name: String,
+    pub playtime: u64, // in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file_size: Option<u64>, // file size in bytes for change detection
+    /// Mods the world was last played with, read from the save when it
+    /// was uploaded; only set for worlds.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub required_mods: Vec<ModRequirement>,
+    /// Game version that last saved the world; only set for worlds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub game_version: Option<String>,
+    /// Device that last uploaded the file.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_upload: Option<UploadRecord>,
+    /// Size of what was last uploaded; differs from `file_size` when a
+    /// snapshot of a world in use went up.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uploaded_size: Option<u64>,
+    /// SHA-256 of what was last uploaded, in hex.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sha256: Option<String>,
+    /// How the copy in each storage profile the file goes to stands. Empty
+    /// for files last uploaded before copies were tracked.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub destinations: BTreeMap<String, DestinationStatus>,
+}
+
+impl FileInfo {
+    /// Whether `key` names a world file in the Saves scope.
+    pub fn is_world_key(key: &str) -> bool {
+        let (scope, relative) = SyncScope::split_key(key);
+        scope == SyncScope::Saves && relative.ends_with(".vcdbs")
+    }
+
+    /// The size the stored copies should have.
+    pub fn stored_size(&self) -> Option<u64> {
+        self.uploaded_size.or(self.file_size)
+    }
+
+    /// Whether the copy in `profile` holds the latest upload.
+    pub fn is_current_in(&self, profile: &str) -> bool {
+        self.destinations
+            .get(profile)
+            .is_some_and(|status| status.error.is_none() && status.last_upload == self.last_upload)
+    }
+}
+
+/// The copy of a file in one storage profile.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DestinationStatus {
+    /// The upload the copy comes from.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_upload: Option<UploadRecord>,
+    /// Why the latest upload there failed; cleared by one that succeeds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// One file sent to its storage profiles during an upload.
+#[derive(Debug, Clone)]
+pub struct FileUpload {
+    /// The upload the new copies belong to.
+    pub record: UploadRecord,
+    /// Size and SHA-256 of what was sent.
+    pub size: u64,
+    pub sha256: String,
+    /// Every profile tried, with the error when it failed.
+    pub results: Vec<(String, Option<String>)>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct UploadManifest {
+    pub files: HashMap<String, FileInfo>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct VintageProgramData {
+    pub last_opened: u64,
+    pub current_used_bucket: String,
+    pub all_file_info: HashMap<String, UploadManifest>,
+    #[serde(default)]
+    pub folder_settings: HashMap<String, FolderSettings>,
+    #[serde(default)]
+    pub auto_sync: AutoSyncSettings,
+    /// Whether the client profile or a dedicated server is synced. Read on
+    /// its own by `profile::active_profile`.
+    #[serde(default)]
+    pub profile: DataProfile,
+    #[serde(default)]
+    pub hooks: HookSettings,
+    /// This machine; see `device::device_identity`.
+    #[serde(default)]
+    pub device: DeviceIdentity,
+    #[serde(default)]
+    pub locking: LockSettings,
+    /// Worlds this device has checked out, per folder bucket.
+    #[serde(default)]
+    pub checked_out: HashMap<String, BTreeSet<String>>,
+    /// Saves rules written before scopes existed; moved into
+    /// `folder_settings` on load.
+    #[serde(default, skip_serializing)]
+    sync_filters: HashMap<String, SyncFilter>,
+}
+
+/// What a folder bucket syncs: which scopes are enabled and the
+/// include/exclude rules of each scope.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FolderSettings {
+    #[serde(default = "default_scopes")]
+    pub scopes: Vec<SyncScope>,
+    /// Scopes without an entry use `SyncScope::default_filter()`.
+    #[serde(default)]
+    pub filters: HashMap<SyncScope, SyncFilter>,
+    #[serde(default)]
+    pub schedules: Vec<Schedule>,
+}
+
+fn default_scopes() -> Vec<SyncScope> {
+    vec![SyncScope::Saves]
+}
+
+impl Default for FolderSettings {
+    fn default() -> Self {
+        FolderSettings {
+            scopes: default_scopes(),
+            filters: HashMap::new(),
+            schedules: Vec::new(),
+        }
+    }
+}
+
+impl FolderSettings {
+    pub fn filter(&self, scope: SyncScope) -> SyncFilter {
+        self.filters
+            .get(&scope)
+            .cloned()
+            .unwrap_or_else(|| scope.default_filter())
+    }
+
+    /// Enabled scopes in a stable order.
+    pub fn enabled_scopes(&self) -> Vec<SyncScope> {
+        SyncScope::ALL
+            .into_iter()
+            .filter(|scope| self.scopes.contains(scope))
+            .collect()
+    }
+}
+
+/// Syncing driven by the game and its saves instead of the buttons.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AutoSyncSettings {
+    /// Upload the current folder bucket after the game exits.
+    pub upload_on_exit: bool,
+    /// Seconds to wait after the game exits before uploading.
+    pub upload_delay_secs: u64,
+    /// Download the latest saves before launching the game from here.
+    pub download_before_launch: bool,
+    /// Upload worlds as they change on disk.
+    pub watch_saves: bool,
+    /// Seconds without writes before changed worlds are uploaded.
+    pub quiet_period_secs: u64,
+}
+
+impl Default for AutoSyncSettings {
+    fn default() -> Self {
+        AutoSyncSettings {
+            upload_on_exit: false,
+            upload_delay_secs: 30,
+            download_before_launch: false,
+            watch_saves: false,
+            quiet_period_secs: 30,
+        }
+    }
+}
+
+/// How world leases (see `world_lock`) behave.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LockSettings {
+    /// Minutes a lease lasts without being renewed before other devices may
+    /// break it.
+    pub lease_minutes: u64,
+}
+
+impl LockSettings {
+    pub fn lease(&self) -> Duration {
+        Duration::from_secs(self.lease_minutes.max(1) * 60)
+    }
+}
+
+impl Default for LockSettings {
+    fn default() -> Self {
+        LockSettings { lease_minutes: 30 }
+    }
+}
+
+impl VintageProgramData {
+    pub fn folder_settings(&self, folder_bucket: &str) -> FolderSettings {
+        self.folder_settings.get(folder_bucket).cloned().unwrap_or_default()
+    }
+
+    fn migrate_legacy_filters(&mut self) {
+        for (folder_bucket, filter) in self.sync_filters.drain() {
+            self.folder_settings
+                .entry(folder_bucket)
+                .or_default()
+                .filters
+                .entry(SyncScope::Saves)
+                .or_insert(filter);
+        }
+    }
+}
+
+pub fn manifest_file_path() -> Result<PathBuf, Error> {
+    Ok(resolve_program_data_dir()?.join(MANIFEST_FILE_NAME))
+}
+
+fn legacy_manifest_file_path(save_root: &Path) -> PathBuf {
+    save_root.join(LEGACY_MANIFEST_FILE_NAME)
+}
+
+/// Moves a manifest left in the Saves folder by older versions into the
+/// program data directory. Does nothing once the new manifest exists.
+pub fn migrate_legacy_manifest() -> Result<(), Error> {
+    let manifest_path = manifest_file_path()?;
+    if manifest_path.exists() {
+        return Ok(());
+    }
+
+    let Ok(save_root) = resolve_save_dir() else {
+        return Ok(());
+    };
+    let legacy_path = legacy_manifest_file_path(&save_root);
+    if legacy_path.exists() {
+        println!(
+            "Migrating manifest from {} to {}",
+            legacy_path.display(),
+            manifest_path.display()
+        );
+        move_file(&legacy_path, &manifest_path)
+            .map_err(|e| Error::other(format!("Failed to migrate program data: {}", e)))?;
+    }
+    Ok(())
+}
+
+/// Records `files` (manifest key and local path) as in sync in
+/// `bucket_name`. Those in `uploaded` are stamped with their upload, the
+/// copies it made in each profile and what was sent; worlds among them are
+/// tagged with the mods and the game version found in the save. The others
+/// keep the entries they have.
+pub fn update_vintage_program_data(
+    bucket_name: String,
+    files: &[(String, PathBuf)],
+    uploaded: &HashMap<&str, FileUpload>,
+) -> Result<bool, Error> {
+    println!("Updating vintage program data for bucket: {}", bucket_name);
+
+    update_program_data(|current_manifest| {
+        current_manifest.current_used_bucket = bucket_name.clone();
+        current_manifest.last_opened = 0;
+
+        for (key, file_path) in files {
+            let file_name = file_path.file_name().unwrap().to_string_lossy().to_string();
+            let is_world = FileInfo::is_world_key(key);
+            let previous = current_manifest
+                .all_file_info
+                .get(&bucket_name)
+                .and_then(|folder_manifest| folder_manifest.files.get(key));
+            let upload = uploaded.get(key.as_str());
+            if upload.is_none() && previous.is_some() {
+                // Already in sync; the entry describes the save that was uploaded.
+                continue;
+            }
+            let mut destinations = previous.map(|file_info| file_info.destinations.clone()).unwrap_or_default();
+            let mut uploaded_size = previous.and_then(|file_info| file_info.uploaded_size);
+            let mut sha256 = previous.and_then(|file_info| file_info.sha256.clone());
+            let last_upload = match upload {
+                Some(upload) => {
+                    for (profile, error) in &upload.results {
+                        let status = destinations.entry(profile.clone()).or_default();
+                        match error {
+                            None => {
+                                status.last_upload = Some(upload.record.clone());
+                                status.error = None;
+                            }
+                            Some(error) => status.error = Some(error.clone()),
+                        }
+                    }
+                    uploaded_size = Some(upload.size);
+                    sha256 = Some(upload.sha256.clone());
+                    Some(upload.record.clone())
+                }
+                None => previous.and_then(|file_info| file_info.last_upload.clone()),
+            };
+            let file_info = FileInfo {
+                world_name: file_name,
+                playtime: 0,
+                file_size: Some(file_path.metadata()?.len()),
+                required_mods: if is_world {
+                    savegame_mods(file_path).unwrap_or_default()
+                } else {
+                    Vec::new()
+                },
+                game_version: if is_world { savegame_version(file_path) } else { None },
+                last_upload,
+                uploaded_size,
+                sha256,
+                destinations,
+            };
+            current_manifest.all_file_info
+                .entry(bucket_name.clone())
+                .or_default()
+                .files
+                .insert(key.clone(), file_info);
+        }
+
+        Ok(true)
+    })
+}
+
+/// Copies the remote manifest entries of freshly downloaded files into the
+/// local manifest so they are not mistaken for local changes later.
+pub fn record_downloaded_files(bucket_name: &str, entries: Vec<(String, FileInfo)>) -> Result<(), Error> {
+    update_program_data(|current_manifest| {
+        let folder_manifest = current_manifest
+            .all_file_info
+            .entry(bucket_name.to_string())
+            .or_default();
+        for (key, file_info) in entries {
+            folder_manifest.files.insert(key, file_info);
+        }
+        Ok(())
+    })
+}
+
+/// Reads the program data, lets `change` edit it and writes it back. The
+/// UI, the watchers and the scheduler all do this from their own threads,
+/// so it happens under a process-wide lock; nothing is written when
+/// `change` fails. `change` must not call back into this.
+pub fn update_program_data<T>(change: impl FnOnce(&mut VintageProgramData) -> Result<T, Error>) -> Result<T, Error> {
+    let _guard = PROGRAM_DATA_LOCK.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut program_data = get_manifest_info()?;
+    let result = change(&mut program_data)?;
+    write_program_data(&program_data)?;
+    Ok(result)
+}
+
+/// Writes the program data to a temporary file next to the manifest and
+/// moves it into place, so readers never see half a file.
+fn write_program_data(program_data: &VintageProgramData) -> Result<(), Error> {
+    let manifest_path = manifest_file_path()?;
+    let data = serde_json::to_string_pretty(&program_data).map_err(|e| Error::other(format!("Failed to save program data: {}", e)))?;
+    let mut temp_path = manifest_path.clone().into_os_string();
+    temp_path.push(format!(".{}.tmp", process::id()));
+    let temp_path = PathBuf::from(temp_path);
+    let written = fs::File::create(&temp_path)
+        .and_then(|mut file| {
+            file.write_all(data.as_bytes())?;
+            file.sync_all()
+        })
+        .and_then(|()| fs::rename(&temp_path, &manifest_path));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&temp_path);
+        return Err(io::Error::new(e.kind(), format!("Failed to save program data: {}", e)).into());
+    }
+    Ok(())
+}
+
+pub fn set_sync_filter(folder_bucket: &str, scope: SyncScope, filter: SyncFilter) -> Result<(), Error> {
+    // Reject bad globs before they end up in the manifest.
+    filter.compile()?;
+    update_program_data(|program_data| {
+        program_data
+            .folder_settings
+            .entry(folder_bucket.to_string())
+            .or_default()
+            .filters
+            .insert(scope, filter);
+        Ok(())
+    })
+}
+
+pub fn set_scope_enabled(folder_bucket: &str, scope: SyncScope, enabled: bool) -> Result<(), Error> {
+    update_program_data(|program_data| {
+        let settings = program_data
+            .folder_settings
+            .entry(folder_bucket.to_string())
+            .or_default();
+        settings.scopes.retain(|existing| *existing != scope);
+        if enabled {
+            settings.scopes.push(scope);
+        }
+        Ok(())
+    })
+}
+
+pub fn set_schedules(folder_bucket: &str, schedules: Vec<Schedule>) -> Result<(), Error> {
+    for schedule in &schedules {
+        schedule.validate()?;
+    }
+    update_program_data(|program_data| {
+        program_data
+            .folder_settings
+            .entry(folder_bucket.to_string())
+            .or_default()
+            .schedules = schedules;
+        Ok(())
+    })
+}
+
+/// Stores `timestamp` as the last run of the schedule at `index`.
+pub fn record_schedule_run(folder_bucket: &str, index: usize, timestamp: i64) -> Result<(), Error> {
+    update_program_data(|program_data| {
+        let schedule = program_data
+            .folder_settings
+            .get_mut(folder_bucket)
+            .and_then(|settings| settings.schedules.get_mut(index))
+            .ok_or_else(|| Error::NotFound(format!("schedule {} of {} no longer exists", index, folder_bucket)))?;
+        schedule.last_run = Some(timestamp);
+        Ok(())
+    })
+}
+
+pub fn set_auto_sync(settings: AutoSyncSettings) -> Result<(), Error> {
+    update_program_data(|program_data| {
+        program_data.auto_sync = settings;
+        Ok(())
+    })
+}
+
+/// Marks `worlds` of `folder_bucket` as checked out to this device, or no
+/// longer checked out.
+pub fn set_checked_out(folder_bucket: &str, worlds: &[String], checked_out: bool) -> Result<(), Error> {
+    if worlds.is_empty() {
+        return Ok(());
+    }
+    update_program_data(|program_data| {
+        let held = program_data.checked_out.entry(folder_bucket.to_string()).or_default();
+        for world in worlds {
+            if checked_out {
+                held.insert(world.clone());
+            } else {
+                held.remove(world);
+            }
+        }
+        program_data.checked_out.retain(|_, worlds| !worlds.is_empty());
+        Ok(())
+    })
+}
+
+pub fn set_hooks(hooks: HookSettings) -> Result<(), Error> {
+    update_program_data(|program_data| {
+        program_data.hooks = hooks;
+        Ok(())
+    })
+}
+
+pub fn set_profile(profile: DataProfile) -> Result<(), Error> {
+    if matches!(&profile, DataProfile::Server(server) if server.data_path.trim().is_empty()) {
+        return Err(Error::Config("the server profile needs the server's data path".to_string()));
+    }
+    update_program_data(|program_data| {
+        program_data.profile = profile;
+        Ok(())
+    })
+}
+
+pub fn manifest_status_message() -> Result<String, Error> {
+    let manifest_info = get_manifest_info()?;
+    let total_files: usize = manifest_info.all_file_info.values()
+        .map(|manifest| manifest.files.len())
+        .sum();
+    Ok(format!("Existing manifest found. Tracking {} files.", total_files))
+}
+
+pub fn get_manifest_info() -> Result<VintageProgramData, Error> {
+    migrate_legacy_manifest()?;
+
+    let manifest_path = manifest_file_path()?;
+    if manifest_path.exists() {
+        let data = fs::read_to_string(manifest_path).map_err(|e| io::Error::new(e.kind(), format!("Failed to read program data: {}", e)))?;
+        let mut program_data: VintageProgramData = serde_json::from_str(&data).map_err(|e| Error::Integrity(format!("Failed to parse program data: {}", e)))?;
+        program_data.migrate_legacy_filters();
+        Ok(program_data)
+    } else {
+        Ok(VintageProgramData::default())
+    }
+}
@@ -0,0 +1,218 @@
+use protobuf::rt::WireType;
+use protobuf::CodedInputStream;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::Read;
+use std::path::Path;
+
+use crate::error::Error;
+use crate::game_version::{savegame_record, short_fields};
+
+/// Mods every install has, which never show up in the Mods folder.
+const BUILT_IN_MODS: [&str; 3] = ["game", "survival", "creative"];
+
+/// A mod a world needs, as recorded in its manifest entry.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModRequirement {
+    pub mod_id: String,
+    pub version: String,
+}
+
+/// Something about the local Mods folder that will likely break a world
+/// after it is downloaded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ModMismatch {
+    Missing {
+        mod_id: String,
+        required_version: String,
+    },
+    VersionMismatch {
+        mod_id: String,
+        required_version: String,
+        installed_version: String,
+    },
+}
+
+impl fmt::Display for ModMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ModMismatch::Missing { mod_id, required_version } => {
+                write!(f, "mod {} {} is not installed", mod_id, required_version)
+            }
+            ModMismatch::VersionMismatch { mod_id, required_version, installed_version } => write!(
+                f,
+                "mod {} is {} locally but the world was saved with {}",
+                mod_id, installed_version, required_version
+            ),
+        }
+    }
+}
+
+/// Mods the world in `path` was last played with, from the mod list the
+/// game keeps in its savegame record. `None` when the file is not a
+/// readable savegame.
+pub fn savegame_mods(path: &Path) -> Option<Vec<ModRequirement>> {
+    let mut mods = mod_list(&savegame_record(path)?);
+    mods.sort_by(|a, b| a.mod_id.cmp(&b.mod_id));
+    mods.dedup_by(|a, b| a.mod_id == b.mod_id);
+    Some(mods)
+}
+
+/// Like the game version (see `game_version::savegame_version`), the mod
+/// list is found without relying on field numbers: each entry is a top
+/// level field holding a small message of just a mod ID and a version.
+fn mod_list(data: &[u8]) -> Vec<ModRequirement> {
+    short_fields(data, 256)
+        .iter()
+        .filter_map(|bytes| mod_entry(bytes))
+        .filter(|requirement| !BUILT_IN_MODS.contains(&requirement.mod_id.as_str()))
+        .collect()
+}
+
+fn mod_entry(bytes: &[u8]) -> Option<ModRequirement> {
+    let mut input = CodedInputStream::from_bytes(bytes);
+    let mut strings = Vec::new();
+    while let Some(tag) = input.read_raw_tag_or_eof().ok()? {
+        if WireType::new(tag & 7)? != WireType::LengthDelimited || strings.len() == 2 {
+            return None;
+        }
+        strings.push(String::from_utf8(input.read_bytes().ok()?).ok()?);
+    }
+    let [mod_id, version] = <[String; 2]>::try_from(strings).ok()?;
+    let is_mod_id = !mod_id.is_empty() && mod_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    let is_version = version.starts_with(|c: char| c.is_ascii_digit())
+        && version.contains('.')
+        && version.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+'));
+    (is_mod_id && is_version).then_some(ModRequirement { mod_id, version })
+}
+
+/// Mods that are installed and enabled in `data_root`, what a downloaded
+/// world is checked against.
+pub fn enabled_mods(data_root: &Path) -> Result<Vec<ModRequirement>, Error> {
+    let disabled = disabled_mods(data_root);
+    let mut mods: Vec<ModRequirement> = installed_mods(data_root)?
+        .into_iter()
+        .filter(|m| {
+            !disabled.contains(&m.mod_id) && !disabled.contains(&format!("{}@{}", m.mod_id, m.version))
+        })
+        .collect();
+    mods.sort_by(|a, b| a.mod_id.cmp(&b.mod_id));
+    mods.dedup_by(|a, b| a.mod_id == b.mod_id);
+    Ok(mods)
+}
+
+/// Every mod in the `Mods` folder that has a readable `modinfo.json`, either
+/// packed in a zip or in an unpacked mod folder.
+pub fn installed_mods(data_root: &Path) -> Result<Vec<ModRequirement>, Error> {
+    let mods_dir = data_root.join("Mods");
+    if !mods_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+
+    let mut mods = Vec::new();
+    for entry in fs::read_dir(&mods_dir)? {
+        let path = entry?.path();
+        let modinfo = if path.is_dir() {
+            fs::read_to_string(path.join("modinfo.json")).ok()
+        } else if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip")) {
+            read_zipped_modinfo(&path)
+        } else {
+            None
+        };
+
+        if let Some(requirement) = modinfo.as_deref().and_then(parse_modinfo) {
+            mods.push(requirement);
+        }
+    }
+    Ok(mods)
+}
+
+/// Compares what a world requires with what is installed locally.
+pub fn compare_mods(required: &[ModRequirement], installed: &[ModRequirement]) -> Vec<ModMismatch> {
+    let installed: HashMap<&str, &str> = installed
+        .iter()
+        .map(|m| (m.mod_id.as_str(), m.version.as_str()))
+        .collect();
+
+    required
+        .iter()
+        .filter_map(|req| match installed.get(req.mod_id.as_str()) {
+            None => Some(ModMismatch::Missing {
+                mod_id: req.mod_id.clone(),
+                required_version: req.version.clone(),
+            }),
+            Some(version) if *version != req.version => Some(ModMismatch::VersionMismatch {
+                mod_id: req.mod_id.clone(),
+                required_version: req.version.clone(),
+                installed_version: version.to_string(),
+            }),
+            Some(_) => None,
+        })
+        .collect()
+}
+
+fn read_zipped_modinfo(path: &Path) -> Option<String> {
+    let file = fs::File::open(path).ok()?;
+    let mut archive = zip::ZipArchive::new(file).ok()?;
+    let index = (0..archive.len()).find(|&i| {
+        archive
+            .name_for_index(i)
+            .is_some_and(|name| name.eq_ignore_ascii_case("modinfo.json"))
+    })?;
+    let mut contents = String::new();
+    archive.by_index(index).ok()?.read_to_string(&mut contents).ok()?;
+    Some(contents)
+}
+
+/// modinfo.json keys are case-insensitive for the game, so look them up the
+/// same way. When `modid` is missing the game derives it from the name.
+fn parse_modinfo(contents: &str) -> Option<ModRequirement> {
+    let json: Value = serde_json::from_str(contents.trim_start_matches('\u{feff}')).ok()?;
+    let object = json.as_object()?;
+    let field = |name: &str| {
+        object
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .and_then(|(_, value)| value.as_str())
+            .map(str::to_string)
+    };
+
+    let mod_id = field("modid").or_else(|| {
+        field("name").map(|name| {
+            name.chars()
+                .filter(|c| c.is_ascii_alphanumeric())
+                .collect::<String>()
+                .to_lowercase()
+        })
+    })?;
+    if mod_id.is_empty() {
+        return None;
+    }
+    Some(ModRequirement {
+        mod_id,
+        version: field("version").unwrap_or_default(),
+    })
+}
+
+/// Entries of `disabledMods` in clientsettings.json, stored as `modid` or
+/// `modid@version`.
+fn disabled_mods(data_root: &Path) -> HashSet<String> {
+    fs::read_to_string(data_root.join("clientsettings.json"))
+        .ok()
+        .and_then(|contents| serde_json::from_str::<Value>(&contents).ok())
+        .and_then(|settings| {
+            settings
+                .as_object()?
+                .iter()
+                .find(|(key, _)| key.eq_ignore_ascii_case("disabledMods"))
+                .and_then(|(_, value)| value.as_array().cloned())
+        })
+        .unwrap_or_default()
+        .iter()
+        .filter_map(|value| value.as_str().map(str::to_string))
+        .collect()
+}
@@ -0,0 +1,92 @@
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::thread;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::helper_functions::run_shell_command;
+use crate::manifest_info::manifest_file_path;
+
+/// Which Vintage Story installation the data root belongs to.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DataProfile {
+    /// The game client's VintagestoryData profile.
+    #[default]
+    Client,
+    /// A dedicated server started with `--dataPath`.
+    Server(ServerProfile),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerProfile {
+    /// The server's data directory, e.g. `/var/vintagestory/data`.
+    pub data_path: String,
+    /// Run before a snapshot so the server writes its worlds first, e.g.
+    /// `screen -S vs -X stuff '/autosavenow\n'`.
+    #[serde(default)]
+    pub pre_backup_command: Option<String>,
+    /// Seconds to give the server to finish saving after the command.
+    #[serde(default = "default_pre_backup_wait")]
+    pub pre_backup_wait_secs: u64,
+}
+
+fn default_pre_backup_wait() -> u64 {
+    10
+}
+
+impl Default for ServerProfile {
+    fn default() -> Self {
+        ServerProfile {
+            data_path: String::new(),
+            pre_backup_command: None,
+            pre_backup_wait_secs: default_pre_backup_wait(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct StoredProfile {
+    #[serde(default)]
+    profile: DataProfile,
+}
+
+/// The profile in use. Read straight from the program data file because the
+/// full load resolves the save directory, which depends on the profile.
+/// `VS_SERVER_DATA_DIR` switches to the server profile with that data path.
+pub fn active_profile() -> DataProfile {
+    let stored = manifest_file_path()
+        .ok()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|data| serde_json::from_str::<StoredProfile>(&data).ok())
+        .map(|stored| stored.profile)
+        .unwrap_or_default();
+
+    match env::var("VS_SERVER_DATA_DIR") {
+        Ok(data_path) => {
+            let server = match stored {
+                DataProfile::Server(server) => server,
+                DataProfile::Client => ServerProfile::default(),
+            };
+            DataProfile::Server(ServerProfile { data_path, ..server })
+        }
+        Err(_) => stored,
+    }
+}
+
+/// Runs the server profile's pre-backup command, then waits for the server
+/// to finish saving. Does nothing for the client profile.
+pub fn run_pre_backup_command() -> Result<(), Error> {
+    let DataProfile::Server(server) = active_profile() else {
+        return Ok(());
+    };
+    let Some(command) = server.pre_backup_command.filter(|command| !command.trim().is_empty()) else {
+        return Ok(());
+    };
+
+    println!("Running pre-backup command: {}", command);
+    run_shell_command(&command, &[])?;
+    thread::sleep(Duration::from_secs(server.pre_backup_wait_secs));
+    Ok(())
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+
+/// How often progress is reported while a file is being transferred.
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+/// How far an upload, download or snapshot has come.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferProgress {
+    pub transferred_bytes: u64,
+    pub total_bytes: u64,
+    pub current_file: String,
+    pub elapsed_secs: f32,
+    /// Set on the report sent once `current_file` is completely transferred.
+    pub file_done: bool,
+}
+
+impl TransferProgress {
+    pub fn fraction(&self) -> f32 {
+        if self.total_bytes > 0 {
+            self.transferred_bytes as f32 / self.total_bytes as f32
+        } else {
+            0.0
+        }
+    }
+
+    pub fn bytes_per_sec(&self) -> f32 {
+        if self.elapsed_secs > 0.0 {
+            self.transferred_bytes as f32 / self.elapsed_secs
+        } else {
+            0.0
+        }
+    }
+
+    /// Seconds left at the throughput so far, once anything was transferred.
+    pub fn eta_secs(&self) -> Option<u64> {
+        let speed = self.bytes_per_sec();
+        (speed > 0.0).then(|| (self.total_bytes.saturating_sub(self.transferred_bytes) as f32 / speed) as u64)
+    }
+}
+
+/// Called with the progress of a transfer as it goes, from the thread
+/// running the transfer.
+pub type ProgressCallback<'a> = &'a (dyn Fn(TransferProgress) + Sync);
+
+/// Reports the progress of one transfer to an optional callback, throttled
+/// to `PROGRESS_INTERVAL` while a file is in flight.
+pub(crate) struct ProgressReporter<'a> {
+    callback: Option<ProgressCallback<'a>>,
+    transferred_bytes: u64,
+    total_bytes: u64,
+    start_time: Instant,
+    last_sent: Option<Instant>,
+}
+
+impl<'a> ProgressReporter<'a> {
+    pub(crate) fn new(callback: Option<ProgressCallback<'a>>, total_bytes: u64) -> ProgressReporter<'a> {
+        ProgressReporter {
+            callback,
+            transferred_bytes: 0,
+            total_bytes,
+            start_time: Instant::now(),
+            last_sent: None,
+        }
+    }
+
+    /// Counts `bytes` more of `current_file` as transferred.
+    pub(crate) fn advance(&mut self, bytes: u64, current_file: &str) {
+        self.transferred_bytes += bytes;
+        if self.last_sent.is_none_or(|sent| sent.elapsed() >= PROGRESS_INTERVAL) {
+            self.send(current_file, false);
+        }
+    }
+
+    /// Counts `bytes` more of `current_file` and reports right away, for a
+    /// file that is done.
+    pub(crate) fn complete(&mut self, bytes: u64, current_file: &str) {
+        self.transferred_bytes += bytes;
+        self.send(current_file, true);
+    }
+
+    /// Bytes counted so far, to `rewind` to when an attempt is retried.
+    pub(crate) fn transferred(&self) -> u64 {
+        self.transferred_bytes
+    }
+
+    /// Takes back the bytes counted since `transferred` returned `bytes`.
+    pub(crate) fn rewind(&mut self, bytes: u64) {
+        self.transferred_bytes = bytes;
+    }
+
+    /// Reports the progress so far right away.
+    pub(crate) fn send(&mut self, current_file: &str, file_done: bool) {
+        let Some(callback) = self.callback else {
+            return;
+        };
+        callback(TransferProgress {
+            transferred_bytes: self.transferred_bytes,
+            total_bytes: self.total_bytes,
+            current_file: current_file.to_string(),
+            elapsed_secs: self.start_time.elapsed().as_secs_f32(),
+            file_done,
+        });
+        self.last_sent = Some(Instant::now());
+    }
+}
@@ -0,0 +1,87 @@
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+
+use crate::config::Encryption;
+use crate::error::Error;
+use crate::manifest_info::UploadManifest;
+use crate::retry::{with_retry, RetrySettings};
+
+/// Directory inside a folder bucket reserved for this program's own objects.
+/// Nothing below it is ever treated as a synced file.
+pub const REMOTE_META_DIR: &str = ".vscs";
+
+fn remote_manifest_key(prefix: &str) -> String {
+    format!("{}/{}/manifest.json", prefix, REMOTE_META_DIR)
+}
+
+/// Where the snapshot called `name` keeps its copy of the folder bucket.
+pub fn snapshot_prefix(prefix: &str, name: &str) -> String {
+    format!("{}/{}/snapshots/{}", prefix, REMOTE_META_DIR, name)
+}
+
+/// True for object keys (relative to the folder bucket) that belong to the
+/// program rather than to the user's data.
+pub fn is_meta_key(bucket_key: &str) -> bool {
+    bucket_key
+        .strip_prefix(REMOTE_META_DIR)
+        .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// The manifest shared by every device syncing the folder bucket. It carries
+/// what other machines need to know about an uploaded world (e.g. the mods
+/// it requires). A bucket without one yields an empty manifest.
+pub async fn fetch_remote_manifest(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+    retry: &RetrySettings,
+) -> Result<UploadManifest, Error> {
+    let key = remote_manifest_key(prefix);
+    let body = with_retry(retry, "Fetching the remote manifest", || async {
+        let response = match client.get_object().bucket(bucket).key(&key).send().await {
+            Ok(response) => response,
+            Err(err) if err.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(err) => return Err(Error::storage("failed to fetch remote manifest", err)),
+        };
+        let body = response
+            .body
+            .collect()
+            .await
+            .map_err(|err| Error::Network(format!("failed to read remote manifest: {}", err)))?;
+        Ok(Some(body.to_vec()))
+    })
+    .await?;
+
+    let Some(body) = body else {
+        return Ok(UploadManifest::default());
+    };
+    serde_json::from_slice(&body)
+        .map_err(|err| Error::Integrity(format!("failed to parse remote manifest: {}", err)))
+}
+
+pub async fn store_remote_manifest(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+    retry: &RetrySettings,
+    encryption: &Encryption,
+    manifest: &UploadManifest,
+) -> Result<(), Error> {
+    let data = serde_json::to_vec_pretty(manifest)
+        .map_err(|err| Error::other(format!("failed to serialize remote manifest: {}", err)))?;
+    with_retry(retry, "Storing the remote manifest", || async {
+        let request = client
+            .put_object()
+            .bucket(bucket)
+            .key(remote_manifest_key(prefix))
+            .content_type("application/json")
+            .body(ByteStream::from(data.clone()));
+        encryption
+            .apply(request)
+            .send()
+            .await
+            .map_err(|err| Error::storage("failed to store remote manifest", err))
+    })
+    .await?;
+    Ok(())
+}
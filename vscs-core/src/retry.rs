@@ -0,0 +1,152 @@
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::time::Duration;
+
+use crate::error::Error;
+
+/// How storage requests that fail for a temporary reason are retried.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RetrySettings {
+    /// Attempts per request, the first one included.
+    pub max_attempts: u32,
+    /// Wait before the second attempt; it doubles with every further one.
+    pub initial_delay_ms: u64,
+    /// Longest wait between two attempts, also when the storage asks for
+    /// more through `Retry-After`.
+    pub max_delay_secs: u64,
+}
+
+impl Default for RetrySettings {
+    fn default() -> Self {
+        RetrySettings {
+            max_attempts: 4,
+            initial_delay_ms: 500,
+            max_delay_secs: 30,
+        }
+    }
+}
+
+impl RetrySettings {
+    /// The wait after `failures` failed attempts: exponential, capped, and
+    /// jittered between half and all of it so devices that failed together
+    /// don't all come back at once.
+    fn delay(&self, failures: u32) -> Duration {
+        let exponential = Duration::from_millis(self.initial_delay_ms)
+            .saturating_mul(2u32.saturating_pow(failures.saturating_sub(1)))
+            .min(self.max_delay());
+        exponential.mul_f64(0.5 + fastrand::f64() / 2.0)
+    }
+
+    fn max_delay(&self) -> Duration {
+        Duration::from_secs(self.max_delay_secs)
+    }
+}
+
+/// Counts the failed attempts of one request and waits between them.
+pub(crate) struct Backoff {
+    settings: RetrySettings,
+    failures: u32,
+}
+
+impl Backoff {
+    pub(crate) fn new(settings: &RetrySettings) -> Backoff {
+        Backoff {
+            settings: settings.clone(),
+            failures: 0,
+        }
+    }
+
+    /// Waits before trying `what` again after it failed with `err`, or
+    /// gives `err` back when it is permanent or the attempts are used up.
+    pub(crate) async fn wait(&mut self, what: &str, err: Error) -> Result<(), Error> {
+        self.failures += 1;
+        if !err.is_retryable() || self.failures >= self.settings.max_attempts {
+            return Err(err);
+        }
+        let delay = match err.retry_after() {
+            Some(retry_after) => retry_after.min(self.settings.max_delay()),
+            None => self.settings.delay(self.failures),
+        };
+        println!(
+            "{} failed ({}); attempt {} of {} in {:.1}s",
+            what,
+            err,
+            self.failures + 1,
+            self.settings.max_attempts,
+            delay.as_secs_f64()
+        );
+        tokio::time::sleep(delay).await;
+        Ok(())
+    }
+}
+
+/// Runs `operation` until it succeeds, fails for good or runs out of the
+/// attempts `settings` allow. `what` names it in the log.
+pub(crate) async fn with_retry<T, F, Fut>(settings: &RetrySettings, what: &str, mut operation: F) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, Error>>,
+{
+    let mut backoff = Backoff::new(settings);
+    loop {
+        match operation().await {
+            Ok(value) => return Ok(value),
+            Err(err) => backoff.wait(what, err).await?,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttled(retry_after: Duration) -> Error {
+        Error::Storage {
+            message: "slow down".to_string(),
+            retryable: true,
+            retry_after: Some(retry_after),
+        }
+    }
+
+    #[test]
+    fn delay_doubles_up_to_the_maximum_with_jitter() {
+        let settings = RetrySettings {
+            max_attempts: 10,
+            initial_delay_ms: 1000,
+            max_delay_secs: 5,
+        };
+        for (failures, full) in [(1, 1000), (2, 2000), (3, 4000), (4, 5000), (9, 5000)] {
+            let delay = settings.delay(failures);
+            let full = Duration::from_millis(full);
+            assert!(delay >= full / 2 && delay <= full, "{:?} after {} failures", delay, failures);
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_after_is_capped_at_the_maximum_delay() {
+        let settings = RetrySettings {
+            max_attempts: 2,
+            initial_delay_ms: 0,
+            max_delay_secs: 0,
+        };
+        let mut backoff = Backoff::new(&settings);
+        let wait = backoff.wait("Uploading", throttled(Duration::from_secs(3600)));
+        assert!(tokio::time::timeout(Duration::from_secs(5), wait).await.unwrap().is_ok());
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_permanent_errors_and_after_the_last_attempt() {
+        let settings = RetrySettings {
+            max_attempts: 2,
+            initial_delay_ms: 0,
+            max_delay_secs: 0,
+        };
+        let mut backoff = Backoff::new(&settings);
+        assert!(backoff.wait("Uploading", Error::Config("no bucket".to_string())).await.is_err());
+
+        let mut backoff = Backoff::new(&settings);
+        assert!(backoff.wait("Uploading", throttled(Duration::ZERO)).await.is_ok());
+        assert!(backoff.wait("Uploading", throttled(Duration::ZERO)).await.is_err());
+    }
+}
@@ -0,0 +1,161 @@
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::error::Error;
+use crate::game_watcher::{run_watched_upload, WatcherEvent};
+use crate::helper_functions::{file_key, resolve_save_dir};
+use crate::sync_scope::SyncScope;
+use crate::upload_core::{upload_changed, UploadOptions};
+
+/// Granularity at which the watcher thread notices it should stop.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Side files SQLite writes next to a world; a write to one is a write to
+/// the world.
+const SQLITE_SIDE_SUFFIXES: [&str; 3] = ["-journal", "-wal", "-shm"];
+
+/// Size and modification time of the files downloads put in place, so the
+/// watcher doesn't take them for the game's writes and upload them back.
+static DOWNLOADED: Mutex<BTreeMap<PathBuf, (u64, SystemTime)>> = Mutex::new(BTreeMap::new());
+
+/// Remembers that a download just wrote `path`.
+pub(crate) fn note_download(path: &Path) {
+    let Some(stamp) = file_stamp(path) else {
+        return;
+    };
+    if let Ok(mut downloaded) = DOWNLOADED.lock() {
+        downloaded.insert(path.to_path_buf(), stamp);
+    }
+}
+
+/// Whether `path` is still exactly what a download wrote. Forgets the
+/// download once the file changed.
+fn is_downloaded(path: &Path) -> bool {
+    let Ok(mut downloaded) = DOWNLOADED.lock() else {
+        return false;
+    };
+    match downloaded.get(path) {
+        Some(stamp) if file_stamp(path).as_ref() == Some(stamp) => true,
+        Some(_) => {
+            downloaded.remove(path);
+            false
+        }
+        None => false,
+    }
+}
+
+fn file_stamp(path: &Path) -> Option<(u64, SystemTime)> {
+    let metadata = fs::metadata(path).ok()?;
+    Some((metadata.len(), metadata.modified().ok()?))
+}
+
+/// Watches the Saves folder and uploads the worlds written to once they have
+/// been quiet for a while, so an autosave burst becomes a single upload.
+/// Stops when dropped.
+pub struct SaveWatcher {
+    stop: Arc<AtomicBool>,
+    folder_bucket: Arc<Mutex<String>>,
+}
+
+impl SaveWatcher {
+    pub fn start(
+        folder_bucket: String,
+        quiet_period: Duration,
+        events: Sender<WatcherEvent>,
+    ) -> Result<SaveWatcher, Error> {
+        let save_dir = resolve_save_dir()?;
+        let (fs_tx, fs_rx) = mpsc::channel();
+        let mut watcher = RecommendedWatcher::new(fs_tx, notify::Config::default())
+            .map_err(|e| Error::other(format!("failed to watch saves: {}", e)))?;
+        watcher
+            .watch(&save_dir, RecursiveMode::Recursive)
+            .map_err(|e| Error::other(format!("failed to watch {}: {}", save_dir.display(), e)))?;
+
+        let stop = Arc::new(AtomicBool::new(false));
+        let folder_bucket = Arc::new(Mutex::new(folder_bucket));
+        {
+            let stop = stop.clone();
+            let folder_bucket = folder_bucket.clone();
+            thread::spawn(move || {
+                // Keep the OS watch alive for as long as the thread runs.
+                let _watcher = watcher;
+                let mut changed: HashMap<PathBuf, Instant> = HashMap::new();
+
+                while !stop.load(Ordering::Relaxed) {
+                    match fs_rx.recv_timeout(STOP_CHECK_INTERVAL) {
+                        Ok(Ok(event)) => {
+                            if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                                for world in event.paths.iter().filter_map(|path| changed_world(path)) {
+                                    changed.insert(world, Instant::now());
+                                }
+                            }
+                        }
+                        Ok(Err(err)) => println!("Save watcher error: {}", err),
+                        Err(RecvTimeoutError::Timeout) => {}
+                        Err(RecvTimeoutError::Disconnected) => break,
+                    }
+
+                    let settled = changed
+                        .values()
+                        .max()
+                        .is_some_and(|last_write| last_write.elapsed() >= quiet_period);
+                    if settled {
+                        let worlds: Vec<PathBuf> = changed.drain().map(|(world, _)| world).collect();
+                        let folder = folder_bucket.lock().map(|folder| folder.clone()).unwrap_or_default();
+                        upload_worlds(&save_dir, folder, &worlds, &events);
+                    }
+                }
+            });
+        }
+
+        Ok(SaveWatcher { stop, folder_bucket })
+    }
+
+    /// Changes the folder bucket the next changed worlds go to.
+    pub fn set_folder_bucket(&self, folder_bucket: &str) {
+        if let Ok(mut current) = self.folder_bucket.lock() {
+            *current = folder_bucket.to_string();
+        }
+    }
+}
+
+impl Drop for SaveWatcher {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// The world a written path belongs to, if any.
+fn changed_world(path: &Path) -> Option<PathBuf> {
+    let path = path.to_string_lossy();
+    let world = SQLITE_SIDE_SUFFIXES
+        .iter()
+        .find_map(|suffix| path.strip_suffix(suffix))
+        .unwrap_or(&path);
+    world.ends_with(".vcdbs").then(|| PathBuf::from(world))
+}
+
+fn upload_worlds(save_dir: &Path, folder: String, worlds: &[PathBuf], events: &Sender<WatcherEvent>) {
+    let keys: HashSet<String> = worlds
+        .iter()
+        .filter(|world| world.is_file() && !is_downloaded(world))
+        .map(|world| format!("{}{}", SyncScope::Saves.key_prefix(), file_key(save_dir, world)))
+        .collect();
+    if keys.is_empty() {
+        return;
+    }
+
+    let mut names: Vec<String> = keys.iter().cloned().collect();
+    names.sort();
+    let _ = events.send(WatcherEvent::WorldsChanged { worlds: names.clone() });
+    run_watched_upload(events, &names.join(", "), |progress| {
+        upload_changed(folder, keys, Some(progress), UploadOptions::default())
+    });
+}
@@ -0,0 +1,228 @@
+use chrono::{DateTime, Local, TimeZone};
+use croner::Cron;
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::cancel::CancelToken;
+use crate::error::Error;
+use crate::game_process::GameProcesses;
+use crate::game_watcher::{run_watched_upload, WatcherEvent};
+use crate::manifest_info::{get_manifest_info, record_schedule_run};
+use crate::upload_core::{snapshot_save, upload_save, UploadOptions};
+
+/// How often the scheduler looks for due schedules.
+const TICK_INTERVAL: Duration = Duration::from_secs(30);
+/// Granularity at which the scheduler thread notices it should stop.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// When a schedule fires.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScheduleRule {
+    /// Every `minutes` after the previous run.
+    Interval { minutes: u64 },
+    /// Standard five-field cron expression in local time, e.g. `0 3 * * *`.
+    Cron { expression: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScheduledAction {
+    /// Upload what changed, like "Upload now".
+    Upload,
+    /// Upload a point-in-time copy of everything.
+    Snapshot,
+}
+
+impl ScheduledAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            ScheduledAction::Upload => "Upload",
+            ScheduledAction::Snapshot => "Snapshot",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Schedule {
+    pub action: ScheduledAction,
+    pub rule: ScheduleRule,
+    /// Only fire while the game or the dedicated server is running.
+    #[serde(default)]
+    pub only_while_game_running: bool,
+    /// Unix time of the last run. Set without running when the scheduler
+    /// first sees the schedule, so a new schedule waits for its first turn.
+    #[serde(default)]
+    pub last_run: Option<i64>,
+}
+
+impl Schedule {
+    pub fn validate(&self) -> Result<(), Error> {
+        match &self.rule {
+            ScheduleRule::Interval { minutes: 0 } => Err(Error::Config("interval must be at least a minute".to_string())),
+            ScheduleRule::Interval { .. } => Ok(()),
+            ScheduleRule::Cron { expression } => parse_cron(expression).map(|_| ()),
+        }
+    }
+
+    /// First time the schedule is due after `last_run`.
+    pub fn next_due(&self, last_run: DateTime<Local>) -> Option<DateTime<Local>> {
+        match &self.rule {
+            ScheduleRule::Interval { minutes } => {
+                Some(last_run + chrono::Duration::minutes(i64::try_from(*minutes).ok()?))
+            }
+            ScheduleRule::Cron { expression } => parse_cron(expression)
+                .ok()?
+                .find_next_occurrence(&last_run, false)
+                .ok(),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let when = match &self.rule {
+            ScheduleRule::Interval { minutes } => format!("every {} min", minutes),
+            ScheduleRule::Cron { expression } => format!("cron '{}'", expression),
+        };
+        let condition = if self.only_while_game_running {
+            " while the game runs"
+        } else {
+            ""
+        };
+        format!("{} {}{}", self.action.label(), when, condition)
+    }
+}
+
+fn parse_cron(expression: &str) -> Result<Cron, Error> {
+    Cron::new(expression)
+        .parse()
+        .map_err(|e| Error::Config(format!("invalid cron expression '{}': {}", expression, e)))
+}
+
+/// Background thread running the schedules of every folder bucket. Schedules
+/// are re-read on each tick, so edits apply without a restart. Stops when
+/// dropped.
+pub struct Scheduler {
+    stop: Arc<AtomicBool>,
+}
+
+impl Scheduler {
+    pub fn start(events: Sender<WatcherEvent>) -> Scheduler {
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let stop = stop.clone();
+            thread::spawn(move || {
+                let mut next_tick = Instant::now();
+                while !stop.load(Ordering::Relaxed) {
+                    if Instant::now() < next_tick {
+                        thread::sleep(STOP_CHECK_INTERVAL);
+                        continue;
+                    }
+                    next_tick = Instant::now() + TICK_INTERVAL;
+                    run_due_schedules(&events);
+                }
+            });
+        }
+        Scheduler { stop }
+    }
+}
+
+impl Drop for Scheduler {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Runs every schedule whose next due time has passed. Runs missed while the
+/// machine slept are caught up with a single run.
+fn run_due_schedules(events: &Sender<WatcherEvent>) {
+    let Ok(program_data) = get_manifest_info() else {
+        return;
+    };
+    let now = Local::now();
+    let mut game_running = None;
+
+    for (folder_bucket, settings) in &program_data.folder_settings {
+        for (index, schedule) in settings.schedules.iter().enumerate() {
+            let last_run = schedule
+                .last_run
+                .and_then(|timestamp| Local.timestamp_opt(timestamp, 0).single());
+            let Some(last_run) = last_run else {
+                let _ = record_schedule_run(folder_bucket, index, now.timestamp());
+                continue;
+            };
+            if schedule.next_due(last_run).is_none_or(|due| due > now) {
+                continue;
+            }
+            if schedule.only_while_game_running
+                && !*game_running.get_or_insert_with(|| GameProcesses::scan().is_running())
+            {
+                continue;
+            }
+
+            // Recorded up front so a failing run waits for its next turn
+            // instead of being retried on every tick.
+            if let Err(err) = record_schedule_run(folder_bucket, index, now.timestamp()) {
+                println!("Failed to record scheduled run: {}", err);
+                continue;
+            }
+            let _ = events.send(WatcherEvent::ScheduledRun {
+                folder_bucket: folder_bucket.clone(),
+                action: schedule.action,
+            });
+            let what = format!("{} ({})", folder_bucket, schedule.describe());
+            let folder = folder_bucket.clone();
+            let cancel = CancelToken::new();
+            run_watched_upload(events, &what, |progress| match schedule.action {
+                ScheduledAction::Upload => upload_save(
+                    folder,
+                    Some(progress),
+                    UploadOptions { cancel: cancel.clone(), ..UploadOptions::default() },
+                ),
+                ScheduledAction::Snapshot => snapshot_save(folder, Some(progress), &cancel).map(|_| ()),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(rule: ScheduleRule) -> Schedule {
+        Schedule {
+            action: ScheduledAction::Upload,
+            rule,
+            only_while_game_running: false,
+            last_run: None,
+        }
+    }
+
+    #[test]
+    fn interval_is_due_after_its_minutes() {
+        let last_run = Local.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+        let due = schedule(ScheduleRule::Interval { minutes: 90 }).next_due(last_run);
+        assert_eq!(due, Some(Local.with_ymd_and_hms(2024, 5, 1, 11, 30, 0).unwrap()));
+    }
+
+    #[test]
+    fn cron_is_due_at_its_next_occurrence() {
+        let nightly = schedule(ScheduleRule::Cron { expression: "0 3 * * *".to_string() });
+        let last_run = Local.with_ymd_and_hms(2024, 5, 1, 10, 0, 0).unwrap();
+        assert_eq!(nightly.next_due(last_run), Some(Local.with_ymd_and_hms(2024, 5, 2, 3, 0, 0).unwrap()));
+        // A run right at an occurrence waits for the next one.
+        let at_three = Local.with_ymd_and_hms(2024, 5, 2, 3, 0, 0).unwrap();
+        assert_eq!(nightly.next_due(at_three), Some(Local.with_ymd_and_hms(2024, 5, 3, 3, 0, 0).unwrap()));
+    }
+
+    #[test]
+    fn invalid_rules_are_rejected() {
+        assert!(schedule(ScheduleRule::Interval { minutes: 0 }).validate().is_err());
+        let broken = schedule(ScheduleRule::Cron { expression: "every night".to_string() });
+        assert!(broken.validate().is_err());
+        assert_eq!(broken.next_due(Local::now()), None);
+    }
+}
@@ -0,0 +1,196 @@
+use glob::{MatchOptions, Pattern};
+use serde::{Deserialize, Serialize};
+
+use crate::error::Error;
+use crate::manifest_info::LEGACY_MANIFEST_FILE_NAME;
+
+/// Glob rules deciding which files under a sync root take part in a sync.
+/// A file is synced when it matches at least one `include` rule and no
+/// `exclude` rule. Rules without a `/` are matched against the file name
+/// only; rules containing a `/` are matched against the path relative to
+/// the sync root (e.g. `Backups/**`). A leading `/` anchors a rule at the
+/// sync root (e.g. `/clientsettings.json`).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SyncFilter {
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+}
+
+impl Default for SyncFilter {
+    fn default() -> Self {
+        SyncFilter {
+            include: vec!["*.vcdbs".to_string()],
+            exclude: default_excludes(),
+        }
+    }
+}
+
+/// Files that are never worth syncing: SQLite side files, editor/temp
+/// leftovers and our own legacy manifest.
+pub fn default_excludes() -> Vec<String> {
+    [
+        "*-journal",
+        "*-wal",
+        "*-shm",
+        "*.tmp",
+        "*.bak",
+        "*~",
+        LEGACY_MANIFEST_FILE_NAME,
+    ]
+    .iter()
+    .map(|rule| rule.to_string())
+    .collect()
+}
+
+impl SyncFilter {
+    pub fn compile(&self) -> Result<SyncMatcher, Error> {
+        Ok(SyncMatcher {
+            include: compile_rules(&self.include)?,
+            exclude: compile_rules(&self.exclude)?,
+        })
+    }
+}
+
+struct Rule {
+    pattern: Pattern,
+    match_full_path: bool,
+}
+
+/// Compiled form of a [`SyncFilter`].
+pub struct SyncMatcher {
+    include: Vec<Rule>,
+    exclude: Vec<Rule>,
+}
+
+impl SyncMatcher {
+    /// `relative_path` uses `/` separators and is relative to the sync root.
+    pub fn matches(&self, relative_path: &str) -> bool {
+        let file_name = relative_path.rsplit('/').next().unwrap_or(relative_path);
+        let hit = |rule: &Rule| {
+            let candidate = if rule.match_full_path { relative_path } else { file_name };
+            rule.pattern.matches_with(candidate, match_options())
+        };
+        self.include.iter().any(hit) && !self.exclude.iter().any(hit)
+    }
+
+    /// Whether a file inside the directory `relative_dir` could match, so
+    /// walking it is worth it. Only rules matching from the sync root can
+    /// rule a directory out.
+    pub fn may_match_inside(&self, relative_dir: &str) -> bool {
+        self.include.iter().any(|rule| rule.may_match_inside(relative_dir))
+    }
+}
+
+impl Rule {
+    fn may_match_inside(&self, relative_dir: &str) -> bool {
+        if !self.match_full_path {
+            return true;
+        }
+        let mut rule_parts = self.pattern.as_str().split('/');
+        for dir_part in relative_dir.split('/') {
+            match rule_parts.next() {
+                Some("**") => return true,
+                Some(part) => {
+                    let matches = Pattern::new(part).is_ok_and(|part| part.matches_with(dir_part, match_options()));
+                    if !matches {
+                        return false;
+                    }
+                }
+                None => return false,
+            }
+        }
+        // The file itself still needs a part.
+        rule_parts.next().is_some()
+    }
+}
+
+fn match_options() -> MatchOptions {
+    MatchOptions {
+        case_sensitive: true,
+        require_literal_separator: true,
+        require_literal_leading_dot: false,
+    }
+}
+
+fn compile_rules(rules: &[String]) -> Result<Vec<Rule>, Error> {
+    rules
+        .iter()
+        .map(|rule| {
+            let rule = rule.trim();
+            let match_full_path = rule.contains('/');
+            let rule = rule.trim_start_matches('/');
+            let pattern = Pattern::new(rule)
+                .map_err(|e| Error::Config(format!("invalid sync rule '{}': {}", rule, e)))?;
+            Ok(Rule {
+                pattern,
+                match_full_path,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync_scope::SyncScope;
+
+    fn matcher(include: &[&str], exclude: &[&str]) -> SyncMatcher {
+        SyncFilter {
+            include: include.iter().map(|rule| rule.to_string()).collect(),
+            exclude: exclude.iter().map(|rule| rule.to_string()).collect(),
+        }
+        .compile()
+        .unwrap()
+    }
+
+    #[test]
+    fn default_filter_takes_worlds_only() {
+        let matcher = SyncFilter::default().compile().unwrap();
+        assert!(matcher.matches("world.vcdbs"));
+        assert!(matcher.matches("Old/world.vcdbs"));
+        assert!(!matcher.matches("world.vcdbs-journal"));
+        assert!(!matcher.matches("world.vcdbs.bak"));
+        assert!(!matcher.matches("notes.txt"));
+    }
+
+    #[test]
+    fn rules_with_a_slash_match_the_whole_path() {
+        let no_backups = matcher(&["*"], &["Backups/**"]);
+        assert!(no_backups.matches("world.vcdbs"));
+        assert!(no_backups.matches("Other/Backups/world.vcdbs"));
+        assert!(!no_backups.matches("Backups/world.vcdbs"));
+        assert!(!no_backups.matches("Backups/2024/world.vcdbs"));
+        // A leading slash is the same rule.
+        assert!(!matcher(&["*"], &["/Backups/**"]).matches("Backups/world.vcdbs"));
+    }
+
+    #[test]
+    fn a_leading_slash_anchors_a_file_name_at_the_root() {
+        let settings = matcher(&["/clientsettings.json"], &[]);
+        assert!(settings.matches("clientsettings.json"));
+        assert!(!settings.matches("Backups/clientsettings.json"));
+        assert!(!settings.matches("Mods/somemod/clientsettings.json"));
+    }
+
+    #[test]
+    fn skips_directories_rules_from_the_root_cannot_reach() {
+        let client_settings = SyncScope::ClientSettings.default_filter().compile().unwrap();
+        assert!(client_settings.may_match_inside("Playerdata"));
+        assert!(client_settings.may_match_inside("Macros/nested"));
+        assert!(!client_settings.may_match_inside("Saves"));
+        assert!(!client_settings.may_match_inside("Backups"));
+        let server_config = SyncScope::ServerConfig.default_filter().compile().unwrap();
+        assert!(!server_config.may_match_inside("Playerdata"));
+        // A file-name rule can match at any depth.
+        assert!(SyncFilter::default().compile().unwrap().may_match_inside("Old/Older"));
+    }
+
+    #[test]
+    fn invalid_rules_are_a_config_error() {
+        let filter = SyncFilter {
+            include: vec!["[".to_string()],
+            exclude: Vec::new(),
+        };
+        assert!(matches!(filter.compile(), Err(Error::Config(_))));
+    }
+}
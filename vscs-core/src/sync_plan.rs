@@ -0,0 +1,278 @@
+use serde::Serialize;
+use std::fmt;
+use std::path::PathBuf;
+
+use crate::error::Error;
+use crate::upload_core::DownloadWarning;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    Upload,
+    Download,
+}
+
+/// What a sync does with one file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanAction {
+    Upload,
+    Download,
+    /// Left alone.
+    Skip,
+    /// Changed both here and in the cloud since the last sync. Only
+    /// transferred, replacing the other side's copy, when the sync is told
+    /// to accept this conflict.
+    Conflict,
+}
+
+/// Why a file got its action.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PlanReason {
+    NewFile,
+    SizeChanged,
+    NoSizeRecorded,
+    /// A watcher asked for the file to be uploaded.
+    Requested,
+    Unchanged,
+    MissingLocally,
+    RemoteDiffers,
+    ChangedOnBothSides,
+    ChangedInCloud,
+    /// Removing a file never removes its cloud copy.
+    DeletedLocally,
+    /// Unchanged, but a storage profile it goes to lacks the latest upload.
+    MissingFromProfile,
+}
+
+impl PlanReason {
+    pub fn describe(self) -> &'static str {
+        match self {
+            PlanReason::NewFile => "not uploaded before",
+            PlanReason::SizeChanged => "size changed since the last sync",
+            PlanReason::NoSizeRecorded => "no size recorded at the last sync",
+            PlanReason::Requested => "written to while watched",
+            PlanReason::Unchanged => "unchanged",
+            PlanReason::MissingLocally => "not on this device",
+            PlanReason::RemoteDiffers => "differs from the cloud copy",
+            PlanReason::ChangedOnBothSides => "changed here and in the cloud since the last sync",
+            PlanReason::ChangedInCloud => "changed in the cloud; download it first",
+            PlanReason::DeletedLocally => "deleted here; the cloud copy is kept",
+            PlanReason::MissingFromProfile => "not yet in every storage profile it goes to",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PlannedFile {
+    /// Manifest key, relative to the folder bucket.
+    pub key: String,
+    pub action: PlanAction,
+    pub reason: PlanReason,
+    /// Bytes transferred if the file is: the local size for uploads, the
+    /// remote size for downloads.
+    pub size: u64,
+    #[serde(skip)]
+    pub local_path: PathBuf,
+    #[serde(skip)]
+    pub remote_key: String,
+    /// Storage profiles the file is uploaded to, or the one it is
+    /// downloaded from.
+    #[serde(skip)]
+    pub profiles: Vec<String>,
+}
+
+impl PlannedFile {
+    /// Whether the sync transfers the file without asking.
+    pub fn is_transfer(&self) -> bool {
+        matches!(self.action, PlanAction::Upload | PlanAction::Download)
+    }
+}
+
+/// Everything an upload or a download of a folder bucket would do, worked
+/// out without changing anything.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncPlan {
+    pub folder_bucket: String,
+    pub direction: SyncDirection,
+    pub files: Vec<PlannedFile>,
+    /// What a download needs confirmed; always empty for uploads.
+    pub warnings: Vec<DownloadWarning>,
+}
+
+impl SyncPlan {
+    pub fn new(folder_bucket: &str, direction: SyncDirection, mut files: Vec<PlannedFile>) -> SyncPlan {
+        files.sort_by(|a, b| a.key.cmp(&b.key));
+        SyncPlan {
+            folder_bucket: folder_bucket.to_string(),
+            direction,
+            files,
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn transfers(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files.iter().filter(|file| file.is_transfer())
+    }
+
+    pub fn has_transfers(&self) -> bool {
+        self.transfers().next().is_some()
+    }
+
+    pub fn conflicts(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files.iter().filter(|file| file.action == PlanAction::Conflict)
+    }
+
+    pub fn has_conflicts(&self) -> bool {
+        self.conflicts().next().is_some()
+    }
+
+    /// Manifest keys of the conflicts, to accept them once they were shown.
+    pub fn conflict_keys(&self) -> Vec<String> {
+        self.conflicts().map(|file| file.key.clone()).collect()
+    }
+
+    /// Everything the sync could transfer: the transfers and the conflicts.
+    pub fn transfers_and_conflicts(&self) -> impl Iterator<Item = &PlannedFile> {
+        self.files
+            .iter()
+            .filter(|file| file.is_transfer() || file.action == PlanAction::Conflict)
+    }
+
+    /// The transfers, plus the conflicts whose key is in `accepted`.
+    pub fn transfers_accepting<'a>(&'a self, accepted: &'a [String]) -> impl Iterator<Item = &'a PlannedFile> {
+        self.files.iter().filter(move |file| {
+            file.is_transfer() || (file.action == PlanAction::Conflict && accepted.contains(&file.key))
+        })
+    }
+
+    /// `Error::NeedsConfirmation` listing the conflicts whose key is not in
+    /// `accepted`, e.g. one that came up after the plan was shown.
+    pub fn check_conflicts(&self, accepted: &[String]) -> Result<(), Error> {
+        let conflicts: Vec<&str> = self
+            .conflicts()
+            .map(|file| file.key.as_str())
+            .filter(|key| !accepted.iter().any(|accepted| accepted == key))
+            .collect();
+        if conflicts.is_empty() {
+            return Ok(());
+        }
+        Err(Error::NeedsConfirmation(format!(
+            "changed here and in the cloud since the last sync: {}",
+            conflicts.join(", ")
+        )))
+    }
+
+    pub fn bytes_to_transfer(&self) -> u64 {
+        self.transfers().map(|file| file.size).sum()
+    }
+
+    fn count(&self, action: PlanAction) -> usize {
+        self.files.iter().filter(|file| file.action == action).count()
+    }
+
+    /// One line, e.g. "2 to upload (5.1 MB), 1 conflict, 10 skipped".
+    pub fn summary(&self) -> String {
+        let transfer = match self.direction {
+            SyncDirection::Upload => PlanAction::Upload,
+            SyncDirection::Download => PlanAction::Download,
+        };
+        let verb = match self.direction {
+            SyncDirection::Upload => "upload",
+            SyncDirection::Download => "download",
+        };
+        let mut parts = vec![format!(
+            "{} to {} ({:.1} MB)",
+            self.count(transfer),
+            verb,
+            self.bytes_to_transfer() as f64 / 1024.0 / 1024.0
+        )];
+        let conflicts = self.count(PlanAction::Conflict);
+        if conflicts > 0 {
+            parts.push(format!("{} conflict{}", conflicts, if conflicts == 1 { "" } else { "s" }));
+        }
+        parts.push(format!("{} skipped", self.count(PlanAction::Skip)));
+        parts.join(", ")
+    }
+}
+
+impl fmt::Display for SyncPlan {
+    /// The summary, then every file that is transferred or skipped for a
+    /// reason other than being unchanged.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let direction = match self.direction {
+            SyncDirection::Upload => "Upload",
+            SyncDirection::Download => "Download",
+        };
+        writeln!(f, "{} plan for {}: {}", direction, self.folder_bucket, self.summary())?;
+        for file in self.files.iter().filter(|file| file.reason != PlanReason::Unchanged) {
+            writeln!(
+                f,
+                "  {:<9} {}  {:.1} MB  {}",
+                format!("{:?}", file.action).to_lowercase(),
+                file.key,
+                file.size as f64 / 1024.0 / 1024.0,
+                file.reason.describe()
+            )?;
+        }
+        for warning in &self.warnings {
+            writeln!(f, "  warning   {}", warning)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn file(key: &str, action: PlanAction) -> PlannedFile {
+        PlannedFile {
+            key: key.to_string(),
+            action,
+            reason: PlanReason::NewFile,
+            size: 1024 * 1024,
+            local_path: PathBuf::new(),
+            remote_key: key.to_string(),
+            profiles: Vec::new(),
+        }
+    }
+
+    fn plan() -> SyncPlan {
+        SyncPlan::new(
+            "folder",
+            SyncDirection::Upload,
+            vec![
+                file("b.vcdbs", PlanAction::Conflict),
+                file("a.vcdbs", PlanAction::Upload),
+                file("c.vcdbs", PlanAction::Skip),
+            ],
+        )
+    }
+
+    #[test]
+    fn conflicts_need_confirmation() {
+        let plan = plan();
+        match plan.check_conflicts(&[]) {
+            Err(Error::NeedsConfirmation(message)) => assert!(message.ends_with("b.vcdbs")),
+            other => panic!("expected NeedsConfirmation, got {:?}", other),
+        }
+        assert!(plan.check_conflicts(&plan.conflict_keys()).is_ok());
+        // Accepting the conflicts shown doesn't accept one found later.
+        assert!(plan.check_conflicts(&["z.vcdbs".to_string()]).is_err());
+    }
+
+    #[test]
+    fn conflicts_are_only_transferred_when_accepted() {
+        let plan = plan();
+        let keys = |accepted: &[String]| {
+            plan.transfers_accepting(accepted)
+                .map(|file| file.key.clone())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(keys(&[]), ["a.vcdbs"]);
+        assert_eq!(keys(&plan.conflict_keys()), ["a.vcdbs", "b.vcdbs"]);
+        assert_eq!(plan.summary(), "1 to upload (1.0 MB), 1 conflict, 1 skipped");
+    }
+}
@@ -0,0 +1,133 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::helper_functions::resolve_save_dir;
+use crate::sync_filter::{default_excludes, SyncFilter};
+
+/// A part of the VintagestoryData profile (or a dedicated server's data
+/// directory) that can be synced on its own.
+/// Every scope has its own local root, default rules and remote sub-prefix
+/// inside the folder bucket.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncScope {
+    Saves,
+    Mods,
+    ModConfig,
+    ClientSettings,
+    Screenshots,
+    Backups,
+    /// The dedicated server's serverconfig.json.
+    ServerConfig,
+    /// The dedicated server's per-player data.
+    Playerdata,
+    ServerLogs,
+}
+
+impl SyncScope {
+    pub const ALL: [SyncScope; 9] = [
+        SyncScope::Saves,
+        SyncScope::Mods,
+        SyncScope::ModConfig,
+        SyncScope::ClientSettings,
+        SyncScope::Screenshots,
+        SyncScope::Backups,
+        SyncScope::ServerConfig,
+        SyncScope::Playerdata,
+        SyncScope::ServerLogs,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SyncScope::Saves => "Saves",
+            SyncScope::Mods => "Mods",
+            SyncScope::ModConfig => "Mod config",
+            SyncScope::ClientSettings => "Client settings",
+            SyncScope::Screenshots => "Screenshots",
+            SyncScope::Backups => "Backups",
+            SyncScope::ServerConfig => "Server config",
+            SyncScope::Playerdata => "Server player data",
+            SyncScope::ServerLogs => "Server logs",
+        }
+    }
+
+    /// Directory inside the folder bucket holding this scope's objects.
+    /// Saves stay at the top level so buckets uploaded before scopes existed
+    /// keep working.
+    fn remote_dir(self) -> Option<&'static str> {
+        match self {
+            SyncScope::Saves => None,
+            SyncScope::Mods => Some("_mods"),
+            SyncScope::ModConfig => Some("_modconfig"),
+            SyncScope::ClientSettings => Some("_clientsettings"),
+            SyncScope::Screenshots => Some("_screenshots"),
+            SyncScope::Backups => Some("_backups"),
+            SyncScope::ServerConfig => Some("_serverconfig"),
+            SyncScope::Playerdata => Some("_playerdata"),
+            SyncScope::ServerLogs => Some("_serverlogs"),
+        }
+    }
+
+    /// Local directory this scope syncs.
+    pub fn local_root(self, data_root: &Path) -> Result<PathBuf, Error> {
+        Ok(match self {
+            SyncScope::Saves => resolve_save_dir()?,
+            SyncScope::Mods => data_root.join("Mods"),
+            SyncScope::ModConfig => data_root.join("ModConfig"),
+            SyncScope::ClientSettings => data_root.to_path_buf(),
+            SyncScope::Screenshots => data_root.join("Screenshots"),
+            SyncScope::Backups => data_root.join("Backups"),
+            SyncScope::ServerConfig => data_root.to_path_buf(),
+            SyncScope::Playerdata => data_root.join("Playerdata"),
+            SyncScope::ServerLogs => data_root.join("Logs"),
+        })
+    }
+
+    pub fn default_filter(self) -> SyncFilter {
+        let include: &[&str] = match self {
+            SyncScope::Saves => return SyncFilter::default(),
+            SyncScope::Mods => &["*.zip", "*.dll", "*.cs", "*/**"],
+            SyncScope::ModConfig => &["*"],
+            // Keybinds live in clientsettings.json.
+            SyncScope::ClientSettings => &[
+                "/clientsettings.json",
+                "Playerdata/**",
+                "Macros/**",
+            ],
+            SyncScope::Screenshots => &["*.png", "*.jpg"],
+            SyncScope::Backups => &["*.vcdbs"],
+            SyncScope::ServerConfig => &["/serverconfig.json"],
+            SyncScope::Playerdata => &["*"],
+            SyncScope::ServerLogs => &["*.txt", "*.log", "Archive/**"],
+        };
+        SyncFilter {
+            include: include.iter().map(|rule| rule.to_string()).collect(),
+            exclude: default_excludes(),
+        }
+    }
+
+    /// Key prefix, relative to the folder bucket, under which this scope's
+    /// files are stored both remotely and in the manifest.
+    pub fn key_prefix(self) -> String {
+        match self.remote_dir() {
+            Some(dir) => format!("{}/", dir),
+            None => String::new(),
+        }
+    }
+
+    /// Splits a manifest key (path relative to the folder bucket) into the
+    /// scope owning it and the path relative to that scope's local root.
+    pub fn split_key(key: &str) -> (SyncScope, &str) {
+        for scope in SyncScope::ALL {
+            let relative = scope
+                .remote_dir()
+                .and_then(|dir| key.strip_prefix(dir))
+                .and_then(|rest| rest.strip_prefix('/'));
+            if let Some(relative) = relative {
+                return (scope, relative);
+            }
+        }
+        (SyncScope::Saves, key)
+    }
+}
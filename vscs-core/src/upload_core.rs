@@ -0,0 +1,1583 @@
+use aws_config::retry::RetryConfig;
+use aws_config::BehaviorVersion;
+use aws_sdk_s3::config::{Credentials, Region};
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::Client;
+use chrono::Local;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use tempfile::TempPath;
+use tokio::runtime::Runtime;
+
+use crate::cancel::CancelToken;
+use crate::config::{complete_storage, load_config, resolve_storage, AppConfig, Encryption, StorageConfig};
+use crate::device::{device_identity, metadata_value, DeviceIdentity, UploadRecord};
+use crate::error::Error;
+use crate::game_process::{snapshot_world, GameProcesses};
+use crate::game_version::{installed_game_version, GameVersion};
+use crate::helper_functions::{file_key, file_sha256, resolve_data_root};
+use crate::hooks::{run_post_hook, run_pre_hook, HookStage};
+use crate::manifest_info::{
+    get_manifest_info, record_downloaded_files, set_checked_out, update_vintage_program_data, FileInfo, FileUpload,
+    UploadManifest,
+};
+use crate::mods::{compare_mods, enabled_mods, ModMismatch};
+use crate::profile::run_pre_backup_command;
+use crate::progress::{ProgressCallback, ProgressReporter};
+use crate::remote_manifest::{fetch_remote_manifest, is_meta_key, snapshot_prefix, store_remote_manifest};
+use crate::retry::{with_retry, Backoff, RetrySettings};
+use crate::save_watcher::note_download;
+use crate::sync_filter::SyncMatcher;
+use crate::sync_plan::{PlanAction, PlanReason, PlannedFile, SyncDirection, SyncPlan};
+use crate::sync_scope::SyncScope;
+use crate::world_lock::{acquire_leases, check_leases, player_name, release_leases, renew_leases};
+
+/// Something the user should confirm before a download replaces local files.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DownloadWarning {
+    Mods { world: String, mismatch: ModMismatch },
+    /// The world was last saved by a newer game than the one installed here,
+    /// which cannot load it.
+    NewerGameVersion {
+        world: String,
+        saved_with: String,
+        installed: String,
+    },
+}
+
+impl fmt::Display for DownloadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadWarning::Mods { world, mismatch } => write!(f, "{}: {}", world, mismatch),
+            DownloadWarning::NewerGameVersion { world, saved_with, installed } => write!(
+                f,
+                "{}: saved with game version {} but {} is installed",
+                world, saved_with, installed
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct UploadOptions {
+    /// Manifest keys of files that also changed in the cloud to upload
+    /// anyway, replacing the copy there: the conflicts of the plan the user
+    /// confirmed. Any other conflict fails the upload.
+    pub accepted_conflicts: Vec<String>,
+    /// Stops the upload between files or in the middle of one.
+    pub cancel: CancelToken,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DownloadOptions {
+    /// Go ahead even though `check_download` reported warnings.
+    pub accept_warnings: bool,
+    /// Manifest keys of files that also changed here to download anyway,
+    /// replacing the local copy. Any other conflict fails the download.
+    pub accepted_conflicts: Vec<String>,
+    /// Stops the download between files or in the middle of one.
+    pub cancel: CancelToken,
+}
+
+/// Bucket and key prefix a folder bucket maps to in one storage profile,
+/// with a client for it.
+pub(crate) struct RemoteTarget {
+    pub(crate) profile: String,
+    pub(crate) client: Client,
+    pub(crate) bucket: String,
+    pub(crate) prefix: String,
+    pub(crate) encryption: Encryption,
+    /// Read from the configuration once per transfer.
+    pub(crate) retry: RetrySettings,
+}
+
+impl RemoteTarget {
+    pub(crate) fn remote_key(&self, key: &str) -> String {
+        format!("{}/{}", self.prefix, key)
+    }
+}
+
+/// Every storage profile holding part of a folder bucket, and which of them
+/// each of its files goes to.
+pub(crate) struct Destinations {
+    folder_bucket: String,
+    config: AppConfig,
+    /// Never empty; the folder bucket's own profiles come first.
+    pub(crate) targets: Vec<RemoteTarget>,
+}
+
+impl Destinations {
+    fn target(&self, profile: &str) -> &RemoteTarget {
+        self.targets
+            .iter()
+            .find(|target| target.profile == profile)
+            .unwrap_or(&self.targets[0])
+    }
+
+    /// The targets the file with manifest key `key` is stored in.
+    fn for_key(&self, key: &str) -> Vec<&RemoteTarget> {
+        self.config
+            .destinations(&self.folder_bucket, key)
+            .iter()
+            .map(|profile| self.target(profile))
+            .collect()
+    }
+
+    /// The target `key` is read from, and where its lease is kept.
+    fn primary(&self, key: &str) -> &RemoteTarget {
+        self.for_key(key)[0]
+    }
+
+    /// `keys` grouped by the target they are read from.
+    fn by_primary(&self, keys: &[String]) -> Vec<(&RemoteTarget, Vec<String>)> {
+        self.targets
+            .iter()
+            .map(|target| {
+                let keys = keys
+                    .iter()
+                    .filter(|key| self.primary(key).profile == target.profile)
+                    .cloned()
+                    .collect::<Vec<_>>();
+                (target, keys)
+            })
+            .filter(|(_, keys)| !keys.is_empty())
+            .collect()
+    }
+}
+
+pub(crate) async fn connect(folder_bucket: &str) -> Result<Destinations, Error> {
+    let config = load_config()?;
+    let folder_prefix = config.prefix(folder_bucket);
+    let mut targets = Vec::new();
+    for name in config.folder_profiles(folder_bucket) {
+        let profile = config.profile(&name)?;
+        let storage = complete_storage(&config, profile.storage.clone())?;
+        let client = Client::new(&build_sdk_config(&storage).await);
+        targets.push(RemoteTarget {
+            prefix: profile.key_prefix(&folder_prefix),
+            profile: name,
+            client,
+            bucket: storage.bucket,
+            encryption: profile.encryption,
+            retry: config.retry.clone(),
+        });
+    }
+
+    Ok(Destinations {
+        folder_bucket: folder_bucket.to_string(),
+        config,
+        targets,
+    })
+}
+
+/// Checks that `storage`, e.g. settings not saved yet, can reach its bucket
+/// by listing at most one key, without retrying. An empty application key
+/// uses the stored one.
+pub async fn check_connection(storage: StorageConfig) -> Result<(), Error> {
+    let storage = resolve_storage(storage)?;
+    let client = Client::new(&build_sdk_config(&storage).await);
+    client
+        .list_objects_v2()
+        .bucket(&storage.bucket)
+        .max_keys(1)
+        .send()
+        .await
+        .map_err(|err| Error::storage(format!("failed to list bucket {}", storage.bucket), err))?;
+    Ok(())
+}
+
+pub fn test_connection(storage: StorageConfig) -> Result<(), Error> {
+    let rt = Runtime::new()?;
+    rt.block_on(check_connection(storage))
+}
+
+pub fn upload_save(
+    folder: String,
+    progress: Option<ProgressCallback<'_>>,
+    options: UploadOptions,
+) -> Result<(), Error> {
+    let rt = Runtime::new()?;
+    rt.block_on(run_upload(&folder, progress, options))
+}
+
+/// Uploads only the files with the given manifest keys, whether or not their
+/// size changed. Keys outside the enabled scopes and rules are ignored.
+pub fn upload_changed(
+    folder: String,
+    keys: HashSet<String>,
+    progress: Option<ProgressCallback<'_>>,
+    options: UploadOptions,
+) -> Result<(), Error> {
+    let rt = Runtime::new()?;
+    rt.block_on(upload_files(&folder, Some(&keys), progress, options))
+}
+
+/// Whether the cloud copy changed since this device last synced `key`:
+/// another device uploaded it, or it appeared there while `local_size` says
+/// this device has a different file.
+fn changed_in_cloud(local: Option<&FileInfo>, remote: Option<&FileInfo>, local_size: Option<u64>) -> bool {
+    match (local, remote) {
+        (_, None) => false,
+        (None, Some(remote)) => remote.file_size != local_size,
+        (Some(local), Some(remote)) => {
+            local.file_size != remote.file_size || local.last_upload != remote.last_upload
+        }
+    }
+}
+
+/// The profiles among `profiles`, those the file goes to, whose copy lacks
+/// its latest upload. A file last uploaded before copies were tracked is
+/// taken to be in the first one only.
+fn stale_profiles(local: &FileInfo, profiles: &[String]) -> Vec<String> {
+    profiles
+        .iter()
+        .enumerate()
+        .filter(|(index, profile)| {
+            if local.destinations.is_empty() {
+                *index > 0
+            } else {
+                !local.is_current_in(profile)
+            }
+        })
+        .map(|(_, profile)| profile.clone())
+        .collect()
+}
+
+/// What an upload does with a local file of `size`, given its entry from
+/// the last sync here (`local`) and the one in the manifest of its primary
+/// profile (`remote`). A `requested` file counts as changed. Returns the
+/// profiles among `profiles` it is uploaded to.
+fn classify_upload(
+    local: Option<&FileInfo>,
+    remote: Option<&FileInfo>,
+    size: u64,
+    requested: bool,
+    profiles: Vec<String>,
+) -> (PlanAction, PlanReason, Vec<String>) {
+    // Files are compared by size with what was last synced.
+    let local_change = if requested {
+        Some(PlanReason::Requested)
+    } else {
+        match local.map(|file_info| file_info.file_size) {
+            None => Some(PlanReason::NewFile),
+            Some(None) => Some(PlanReason::NoSizeRecorded),
+            Some(Some(stored_size)) if stored_size != size => Some(PlanReason::SizeChanged),
+            Some(Some(_)) => None,
+        }
+    };
+    match (local_change, changed_in_cloud(local, remote, Some(size))) {
+        (Some(_), true) => (PlanAction::Conflict, PlanReason::ChangedOnBothSides, profiles),
+        (Some(reason), false) => (PlanAction::Upload, reason, profiles),
+        (None, true) => (PlanAction::Skip, PlanReason::ChangedInCloud, profiles),
+        (None, false) => {
+            // Catch up the mirrors a previous upload didn't reach.
+            let stale = local.map(|local| stale_profiles(local, &profiles)).unwrap_or_default();
+            if stale.is_empty() {
+                (PlanAction::Skip, PlanReason::Unchanged, stale)
+            } else {
+                (PlanAction::Upload, PlanReason::MissingFromProfile, stale)
+            }
+        }
+    }
+}
+
+struct UploadPlan {
+    plan: SyncPlan,
+    /// Manifest key and path of every local file that is part of the sync.
+    local_files: Vec<(String, PathBuf)>,
+    /// The unchanged files whose entry in the manifest of their profile
+    /// matches the local one; recorded again along with the uploads.
+    in_sync: Vec<(String, PathBuf)>,
+}
+
+/// Works out what an upload of `folder_bucket` would do. With `only`, just
+/// those manifest keys are considered and they count as changed.
+async fn plan_upload(
+    destinations: &Destinations,
+    folder_bucket: &str,
+    only: Option<&HashSet<String>>,
+) -> Result<UploadPlan, Error> {
+    let data_root = resolve_data_root()?;
+    let manifest = get_manifest_info()?;
+    let settings = manifest.folder_settings(folder_bucket);
+    let folder_manifest = manifest.all_file_info.get(folder_bucket);
+    // A mirror that can't be reached fails its copies when uploading, not
+    // the whole plan.
+    let mut remote_manifests = HashMap::new();
+    for target in &destinations.targets {
+        match fetch_remote_manifest(&target.client, &target.bucket, &target.prefix, &target.retry).await {
+            Ok(remote_manifest) => {
+                remote_manifests.insert(target.profile.as_str(), remote_manifest);
+            }
+            Err(err) if err.stops_transfer() || destinations.targets.len() == 1 => return Err(err),
+            Err(err) => println!("Can't read the manifest in storage profile {}: {}", target.profile, err),
+        }
+    }
+
+    let mut planned = Vec::new();
+    let mut local_files: Vec<(String, PathBuf)> = Vec::new();
+    let mut in_sync = Vec::new();
+
+    for scope in settings.enabled_scopes() {
+        let scope_root = scope.local_root(&data_root)?;
+        if !scope_root.is_dir() {
+            continue;
+        }
+        let matcher = settings.filter(scope).compile()?;
+        let mut files = gather_files(&scope_root, &matcher)?;
+        if let Some(only) = only {
+            files.retain(|file| only.contains(&format!("{}{}", scope.key_prefix(), file_key(&scope_root, file))));
+        }
+
+        for file in files {
+            let key = format!("{}{}", scope.key_prefix(), file_key(&scope_root, &file));
+            let size = fs::metadata(&file)?.len();
+            let local = folder_manifest.and_then(|folder_manifest| folder_manifest.files.get(&key));
+            // Another device's upload shows in the profile files are read from.
+            let primary = destinations.primary(&key);
+            let remote = remote_manifests
+                .get(primary.profile.as_str())
+                .and_then(|remote_manifest| remote_manifest.files.get(&key));
+            let profiles = destinations.config.destinations(folder_bucket, &key);
+            let (action, reason, profiles) = classify_upload(local, remote, size, only.is_some(), profiles);
+
+            if reason == PlanReason::Unchanged && remote.is_some() {
+                in_sync.push((key.clone(), file.clone()));
+            }
+            planned.push(PlannedFile {
+                remote_key: primary.remote_key(&key),
+                key: key.clone(),
+                action,
+                reason,
+                size,
+                local_path: file.clone(),
+                profiles,
+            });
+            local_files.push((key, file));
+        }
+    }
+
+    if local_files.is_empty() && only.is_none() {
+        return Err(Error::NotFound(format!("no files found under {}", data_root.display())));
+    }
+
+    if only.is_none() {
+        let enabled = settings.enabled_scopes();
+        let deleted = folder_manifest
+            .into_iter()
+            .flat_map(|folder_manifest| folder_manifest.files.iter())
+            .filter(|(key, _)| enabled.contains(&SyncScope::split_key(key).0))
+            .filter(|(key, _)| !local_files.iter().any(|(local, _)| local == *key));
+        for (key, file_info) in deleted {
+            planned.push(PlannedFile {
+                key: key.clone(),
+                action: PlanAction::Skip,
+                reason: PlanReason::DeletedLocally,
+                size: file_info.file_size.unwrap_or(0),
+                local_path: PathBuf::new(),
+                remote_key: destinations.primary(key).remote_key(key),
+                profiles: Vec::new(),
+            });
+        }
+    }
+
+    Ok(UploadPlan {
+        plan: SyncPlan::new(folder_bucket, SyncDirection::Upload, planned),
+        local_files,
+        in_sync,
+    })
+}
+
+pub async fn run_upload(
+    folder_bucket: &str,
+    progress: Option<ProgressCallback<'_>>,
+    options: UploadOptions,
+) -> Result<(), Error> {
+    upload_files(folder_bucket, None, progress, options).await
+}
+
+/// Uploads what changed in the enabled scopes. With `only`, just those
+/// manifest keys are considered and they are uploaded unless they also
+/// changed in the cloud. Conflicts fail the upload before anything is sent
+/// unless the options accept them.
+async fn upload_files(
+    folder_bucket: &str,
+    only: Option<&HashSet<String>>,
+    progress: Option<ProgressCallback<'_>>,
+    options: UploadOptions,
+) -> Result<(), Error> {
+    let destinations = connect(folder_bucket).await?;
+    let UploadPlan { plan, local_files, in_sync } = plan_upload(&destinations, folder_bucket, only).await?;
+    plan.check_conflicts(&options.accepted_conflicts)?;
+    let cancel = &options.cancel;
+
+    let pending_uploads: Vec<&PlannedFile> = plan.transfers_accepting(&options.accepted_conflicts).collect();
+    if pending_uploads.is_empty() {
+        return check_in(&destinations, folder_bucket, &local_files).await;
+    }
+
+    // Uploading over a world another device has checked out would lose
+    // whatever is played there.
+    let worlds: Vec<String> = pending_uploads
+        .iter()
+        .filter(|entry| FileInfo::is_world_key(&entry.key))
+        .map(|entry| entry.key.clone())
+        .collect();
+    let device = device_identity()?;
+    for (target, worlds) in destinations.by_primary(&worlds) {
+        check_leases(target, &worlds, &device).await?;
+    }
+
+    let keys: Vec<&str> = pending_uploads.iter().map(|entry| entry.key.as_str()).collect();
+    cancel.check()?;
+    run_pre_hook(HookStage::PreUpload, folder_bucket, keys.iter().copied())?;
+    let result = send_uploads(
+        &destinations,
+        folder_bucket,
+        &pending_uploads,
+        &in_sync,
+        progress,
+        cancel,
+    )
+    .await;
+    run_post_hook(HookStage::PostUpload, folder_bucket, keys, &result);
+    result?;
+    check_in(&destinations, folder_bucket, &local_files).await
+}
+
+/// Releases this device's leases on the synced worlds the game no longer has
+/// open. Uploading a world that is still open is a backup in the middle of a
+/// session, so that world stays checked out.
+async fn check_in(
+    destinations: &Destinations,
+    folder_bucket: &str,
+    local_files: &[(String, PathBuf)],
+) -> Result<(), Error> {
+    let Some(held) = get_manifest_info()?.checked_out.remove(folder_bucket) else {
+        return Ok(());
+    };
+    let processes = GameProcesses::scan();
+    let worlds: Vec<String> = local_files
+        .iter()
+        .filter(|(key, path)| held.contains(key) && !processes.holds_open(path))
+        .map(|(key, _)| key.clone())
+        .collect();
+    if worlds.is_empty() {
+        return Ok(());
+    }
+
+    let device = device_identity()?;
+    for (target, worlds) in destinations.by_primary(&worlds) {
+        release_leases(target, &worlds, &device).await?;
+    }
+    set_checked_out(folder_bucket, &worlds, false)
+}
+
+/// Checks `worlds` out to this device so no other device opens them at the
+/// same time.
+async fn check_out(destinations: &Destinations, folder_bucket: &str, worlds: &[String]) -> Result<(), Error> {
+    if worlds.is_empty() {
+        return Ok(());
+    }
+    let device = device_identity()?;
+    let player = player_name(&resolve_data_root()?, &device);
+    let lease = get_manifest_info()?.locking.lease();
+    for (target, worlds) in destinations.by_primary(worlds) {
+        acquire_leases(target, &worlds, &device, &player, lease).await?;
+    }
+    set_checked_out(folder_bucket, worlds, true)
+}
+
+/// Renews the leases of every world this device has checked out, forgetting
+/// the ones another device took over in the meantime.
+pub fn renew_checkouts() -> Result<(), Error> {
+    let program_data = get_manifest_info()?;
+    if program_data.checked_out.is_empty() {
+        return Ok(());
+    }
+    let lease = program_data.locking.lease();
+    let rt = Runtime::new()?;
+    rt.block_on(async {
+        let device = device_identity()?;
+        for (folder_bucket, worlds) in &program_data.checked_out {
+            let worlds: Vec<String> = worlds.iter().cloned().collect();
+            let destinations = connect(folder_bucket).await?;
+            let mut lost = Vec::new();
+            for (target, worlds) in destinations.by_primary(&worlds) {
+                lost.extend(renew_leases(target, &worlds, &device, lease).await?);
+            }
+            if !lost.is_empty() {
+                println!("No longer checked out in {}: {}", folder_bucket, lost.join(", "));
+                set_checked_out(folder_bucket, &lost, false)?;
+            }
+        }
+        Ok(())
+    })
+}
+
+/// Uploads `pending_uploads` to each of their profiles, then records the
+/// uploaded files and those `in_sync` in the local and the shared
+/// manifests. A copy
+/// that fails even after retries doesn't stop the others and is listed in
+/// the returned `Error::Incomplete`; a file that reached some of its
+/// profiles is recorded with the failed ones marked, so the next upload
+/// catches them up. When the upload is cancelled or can't go on at all,
+/// only the files that made it to a bucket are recorded.
+async fn send_uploads(
+    destinations: &Destinations,
+    folder_bucket: &str,
+    pending_uploads: &[&PlannedFile],
+    in_sync: &[(String, PathBuf)],
+    progress: Option<ProgressCallback<'_>>,
+    cancel: &CancelToken,
+) -> Result<(), Error> {
+    let device = device_identity()?;
+    let record = UploadRecord::now(&device);
+    let local_manifest = get_manifest_info()?.all_file_info.remove(folder_bucket).unwrap_or_default();
+    let total_bytes: u64 = pending_uploads.iter().map(|entry| entry.size).sum();
+    let mut progress = ProgressReporter::new(progress, total_bytes);
+    // While the game runs, worlds may be mid-write; upload snapshots of them.
+    let game_running = GameProcesses::scan().is_running();
+
+    progress.send("", false);
+
+    let mut uploaded: HashMap<&str, FileUpload> = HashMap::new();
+    let mut failed: Vec<(String, Error)> = Vec::new();
+    let mut stopped = None;
+    for entry in pending_uploads {
+        let from_snapshot = game_running && FileInfo::is_world_key(&entry.key);
+        // Catching up a mirror copies the upload the others already hold.
+        let record = match entry.reason {
+            PlanReason::MissingFromProfile => local_manifest
+                .files
+                .get(&entry.key)
+                .and_then(|file_info| file_info.last_upload.clone())
+                .unwrap_or_else(|| record.clone()),
+            _ => record.clone(),
+        };
+        // Every profile gets the same bytes, even of a world in use.
+        let source = match UploadSource::prepare(&entry.local_path, from_snapshot) {
+            Ok(source) => source,
+            Err(err) => {
+                failed.push((entry.key.clone(), err));
+                continue;
+            }
+        };
+        let mut results = Vec::new();
+        for profile in &entry.profiles {
+            let target = destinations.target(profile);
+            let what = format!("Uploading {} to {}", entry.key, profile);
+            let remote_key = target.remote_key(&entry.key);
+            let result = cancel
+                .run(with_retry(&target.retry, &what, || put_file(target, &remote_key, &source, &device)))
+                .await;
+            match result {
+                Ok(()) => results.push((profile.clone(), None)),
+                Err(err) if err.stops_transfer() => {
+                    stopped = Some(err);
+                    break;
+                }
+                Err(err) => {
+                    results.push((profile.clone(), Some(err.to_string())));
+                    failed.push((format!("{} in {}", entry.key, profile), err));
+                }
+            }
+        }
+        if results.iter().any(|(_, error)| error.is_none()) {
+            let upload = FileUpload {
+                record,
+                size: source.size,
+                sha256: source.sha256,
+                results,
+            };
+            uploaded.insert(entry.key.as_str(), upload);
+            progress.complete(entry.size, &entry.key);
+        }
+        if stopped.is_some() {
+            break;
+        }
+    }
+
+    // Files that weren't uploaded keep the entries they have, which may
+    // hold another device's newer upload.
+    let mut recorded: Vec<(String, PathBuf)> = pending_uploads
+        .iter()
+        .filter(|entry| uploaded.contains_key(entry.key.as_str()))
+        .map(|entry| (entry.key.clone(), entry.local_path.clone()))
+        .collect();
+    if stopped.is_none() {
+        recorded.extend(in_sync.iter().cloned());
+    }
+    if !recorded.is_empty() {
+        record_uploads(destinations, folder_bucket, &recorded, &uploaded).await?;
+    }
+    match stopped {
+        Some(err) => Err(err),
+        None if failed.is_empty() => Ok(()),
+        None => Err(Error::Incomplete {
+            failed,
+            total: pending_uploads.iter().map(|entry| entry.profiles.len()).sum(),
+        }),
+    }
+}
+
+/// Records `files` in the local manifest, then shares them with the
+/// other devices through the manifest of each profile holding their latest
+/// upload.
+async fn record_uploads(
+    destinations: &Destinations,
+    folder_bucket: &str,
+    files: &[(String, PathBuf)],
+    uploaded: &HashMap<&str, FileUpload>,
+) -> Result<(), Error> {
+    update_vintage_program_data(folder_bucket.to_string(), files, uploaded)?;
+
+    let local_manifest = get_manifest_info()?
+        .all_file_info
+        .remove(folder_bucket)
+        .unwrap_or_default();
+    let mut first_error = None;
+    for target in &destinations.targets {
+        let stored_here: Vec<(&String, &FileInfo)> = files
+            .iter()
+            .filter_map(|(key, _)| local_manifest.files.get(key).map(|file_info| (key, file_info)))
+            .filter(|(key, file_info)| {
+                let profiles = destinations.config.destinations(folder_bucket, key);
+                profiles.contains(&target.profile) && !stale_profiles(file_info, &profiles).contains(&target.profile)
+            })
+            .collect();
+        if stored_here.is_empty() {
+            continue;
+        }
+        let RemoteTarget { client, bucket, prefix, encryption, retry, .. } = target;
+        let result = async {
+            let mut remote_manifest = fetch_remote_manifest(client, bucket, prefix, retry).await?;
+            for (key, file_info) in stored_here {
+                remote_manifest.files.insert(key.clone(), file_info.clone());
+            }
+            store_remote_manifest(client, bucket, prefix, retry, encryption, &remote_manifest).await
+        }
+        .await;
+        // The other profiles' manifests are still worth updating.
+        if let Err(err) = result {
+            first_error.get_or_insert(err);
+        }
+    }
+    first_error.map_or(Ok(()), Err)
+}
+
+/// What goes up for one file: the file itself, or a consistent copy of a
+/// world the game may be writing to, with the size and hash of what is
+/// sent.
+struct UploadSource {
+    local_path: PathBuf,
+    path: PathBuf,
+    size: u64,
+    sha256: String,
+    _snapshot: Option<TempPath>,
+}
+
+impl UploadSource {
+    fn prepare(local_path: &Path, from_snapshot: bool) -> Result<UploadSource, Error> {
+        let snapshot = if from_snapshot {
+            Some(snapshot_world(local_path)?)
+        } else {
+            None
+        };
+        let path = snapshot.as_deref().unwrap_or(local_path).to_path_buf();
+        Ok(UploadSource {
+            local_path: local_path.to_path_buf(),
+            size: fs::metadata(&path)?.len(),
+            sha256: file_sha256(&path)?,
+            path,
+            _snapshot: snapshot,
+        })
+    }
+}
+
+/// Uploads `source` to `s3_key` of `target`, tagged with the uploading
+/// `device` and the hash of its content.
+async fn put_file(
+    target: &RemoteTarget,
+    s3_key: &str,
+    source: &UploadSource,
+    device: &DeviceIdentity,
+) -> Result<(), Error> {
+    let path = &source.local_path;
+    let body = ByteStream::from_path(&source.path)
+        .await
+        .map_err(|err| Error::LocalIo(io::Error::other(format!("failed to read {}: {}", path.display(), err))))?;
+    let request = target
+        .client
+        .put_object()
+        .bucket(&target.bucket)
+        .key(s3_key)
+        .metadata("device-id", metadata_value(&device.id))
+        .metadata("device-name", metadata_value(&device.name))
+        .metadata("sha256", &source.sha256)
+        .body(body);
+    target
+        .encryption
+        .apply(request)
+        .send()
+        .await
+        .map_err(|err| Error::storage(format!("failed to upload {} to {}", path.display(), s3_key), err))?;
+    Ok(())
+}
+
+/// Uploads every file of the enabled scopes, changed or not, as a
+/// point-in-time copy kept apart from the synced files. Returns the name of
+/// the snapshot.
+/// With the server profile, its pre-backup command runs first so the
+/// server saves its worlds.
+pub fn snapshot_save(
+    folder: String,
+    progress: Option<ProgressCallback<'_>>,
+    cancel: &CancelToken,
+) -> Result<String, Error> {
+    run_pre_backup_command()?;
+    let rt = Runtime::new()?;
+    rt.block_on(run_snapshot(&folder, progress, cancel))
+}
+
+pub async fn run_snapshot(
+    folder_bucket: &str,
+    progress: Option<ProgressCallback<'_>>,
+    cancel: &CancelToken,
+) -> Result<String, Error> {
+    let destinations = connect(folder_bucket).await?;
+    let data_root = resolve_data_root()?;
+    let settings = get_manifest_info()?.folder_settings(folder_bucket);
+
+    let mut files: Vec<(String, PathBuf, u64)> = Vec::new();
+    for scope in settings.enabled_scopes() {
+        let scope_root = scope.local_root(&data_root)?;
+        if !scope_root.is_dir() {
+            continue;
+        }
+        let matcher = settings.filter(scope).compile()?;
+        for file in gather_files(&scope_root, &matcher)? {
+            let key = format!("{}{}", scope.key_prefix(), file_key(&scope_root, &file));
+            let size = fs::metadata(&file)?.len();
+            files.push((key, file, size));
+        }
+    }
+    if files.is_empty() {
+        return Err(Error::NotFound(format!("no files found under {}", data_root.display())));
+    }
+
+    let name = Local::now().format("%Y%m%d-%H%M%S").to_string();
+    let game_running = GameProcesses::scan().is_running();
+    let device = device_identity()?;
+    let total_bytes: u64 = files.iter().map(|(_, _, size)| size).sum();
+    let mut progress = ProgressReporter::new(progress, total_bytes);
+
+    // Each file is snapshotted in every profile it is stored in.
+    let mut stored = Vec::new();
+    for (key, path, size) in files {
+        let from_snapshot = game_running && FileInfo::is_world_key(&key);
+        let source = match UploadSource::prepare(&path, from_snapshot) {
+            Ok(source) => source,
+            Err(err) => {
+                delete_objects(&stored).await;
+                return Err(err);
+            }
+        };
+        for target in destinations.for_key(&key) {
+            let s3_key = format!("{}/{}", snapshot_prefix(&target.prefix, &name), key);
+            let what = format!("Uploading {} to the snapshot in {}", key, target.profile);
+            let result = cancel
+                .run(with_retry(&target.retry, &what, || put_file(target, &s3_key, &source, &device)))
+                .await;
+            if let Err(err) = result {
+                // Half a snapshot is no point-in-time copy; don't leave it behind.
+                delete_objects(&stored).await;
+                return Err(err);
+            }
+            stored.push((target, s3_key));
+        }
+
+        progress.complete(size, &key);
+    }
+
+    Ok(name)
+}
+
+/// Deletes each key from its target's bucket, logging the ones that could
+/// not be.
+async fn delete_objects(objects: &[(&RemoteTarget, String)]) {
+    for (target, key) in objects {
+        let result = with_retry(&target.retry, &format!("Deleting {}", key), || async {
+            target
+                .client
+                .delete_object()
+                .bucket(&target.bucket)
+                .key(key)
+                .send()
+                .await
+                .map_err(|err| Error::storage(format!("failed to delete {}", key), err))
+        })
+        .await;
+        if let Err(err) = result {
+            println!("{}", err);
+        }
+    }
+}
+
+/// A file removed once dropped, including when the transfer using it is
+/// cancelled in the middle of a request.
+struct TempFile(PathBuf);
+
+impl Drop for TempFile {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.0);
+    }
+}
+
+async fn build_sdk_config(storage: &StorageConfig) -> aws_config::SdkConfig {
+    let credentials = Credentials::new(&storage.key_id, &storage.application_key, None, None, "config");
+    // Requests are retried by `retry::with_retry` instead, which also covers
+    // reading bodies and honours Retry-After.
+    let mut loader = aws_config::defaults(BehaviorVersion::latest())
+        .retry_config(RetryConfig::disabled())
+        .credentials_provider(credentials)
+        .region(Region::new(storage.region()));
+    if let Some(endpoint) = storage.endpoint() {
+        loader = loader.endpoint_url(endpoint);
+    }
+    loader.load().await
+}
+
+/// Walks `root` recursively and returns the files accepted by `filter`.
+fn gather_files(root: &Path, matcher: &SyncMatcher) -> Result<Vec<PathBuf>, Error> {
+    let mut files = Vec::new();
+    let mut stack = vec![root.to_path_buf()];
+
+    while let Some(current) = stack.pop() {
+        for entry in fs::read_dir(&current)? {
+            let entry = entry?;
+            let path = entry.path();
+            if path.is_dir() {
+                if matcher.may_match_inside(&file_key(root, &path)) {
+                    stack.push(path);
+                }
+            } else if path.is_file() && matcher.matches(&file_key(root, &path)) {
+                files.push(path);
+            }
+        }
+    }
+
+    Ok(files)
+}
+
+pub fn download_save(
+    folder: String,
+    progress: Option<ProgressCallback<'_>>,
+    options: DownloadOptions,
+) -> Result<(), Error> {
+    let rt = Runtime::new()?;
+    rt.block_on(run_download(&folder, progress, options))
+}
+
+/// What uploading `folder` would do, without uploading anything.
+pub fn preview_upload(folder: String) -> Result<SyncPlan, Error> {
+    let rt = Runtime::new()?;
+    rt.block_on(run_upload_preview(&folder))
+}
+
+pub async fn run_upload_preview(folder_bucket: &str) -> Result<SyncPlan, Error> {
+    let destinations = connect(folder_bucket).await?;
+    Ok(plan_upload(&destinations, folder_bucket, None).await?.plan)
+}
+
+/// What downloading `folder` would do, including the warnings to confirm,
+/// without touching any local file.
+pub fn preview_download(folder: String) -> Result<SyncPlan, Error> {
+    let rt = Runtime::new()?;
+    rt.block_on(run_download_preview(&folder))
+}
+
+pub async fn run_download_preview(folder_bucket: &str) -> Result<SyncPlan, Error> {
+    let destinations = connect(folder_bucket).await?;
+    Ok(plan_download(&destinations, folder_bucket).await?.plan)
+}
+
+/// The objects of a folder bucket one profile holds, with its manifest.
+type Listing<'a> = (&'a RemoteTarget, HashMap<String, u64>, UploadManifest);
+
+/// Lists every profile of the folder bucket and keeps, for each file, the
+/// copy to download: a healthy one, its size matching its manifest entry,
+/// from the latest upload, the first profile winning ties. A profile that
+/// can't be reached is skipped as long as another one can.
+async fn choose_copies(destinations: &Destinations) -> Result<Vec<Listing<'_>>, Error> {
+    let mut listings = Vec::new();
+    let mut unreachable = None;
+    for target in &destinations.targets {
+        let RemoteTarget { client, bucket, prefix, retry, .. } = target;
+        let listing = async {
+            let remote_files = list_remote_files(client, bucket, prefix, retry).await?;
+            let remote_manifest = fetch_remote_manifest(client, bucket, prefix, retry).await?;
+            Ok::<_, Error>((remote_files, remote_manifest))
+        }
+        .await;
+        match listing {
+            Ok((remote_files, remote_manifest)) => listings.push((target, remote_files, remote_manifest)),
+            Err(err) if err.stops_transfer() => return Err(err),
+            Err(err) => {
+                println!("Skipping storage profile {}: {}", target.profile, err);
+                unreachable = Some(err);
+            }
+        }
+    }
+    if let (true, Some(err)) = (listings.is_empty(), unreachable) {
+        return Err(err);
+    }
+
+    // Healthy before corrupt, then newer before older, then profile order.
+    type Rank = (bool, i64, Reverse<usize>);
+    // Per manifest key, the listing holding the chosen copy and its rank.
+    let mut chosen: HashMap<String, (usize, Rank)> = HashMap::new();
+    for (index, (target, remote_files, remote_manifest)) in listings.iter().enumerate() {
+        let key_prefix = format!("{}/", target.prefix);
+        for (remote_key, size) in remote_files {
+            let Some(key) = remote_key.strip_prefix(&key_prefix) else {
+                continue;
+            };
+            let profiles = destinations.config.destinations(&destinations.folder_bucket, key);
+            let Some(order) = profiles.iter().position(|profile| *profile == target.profile) else {
+                continue;
+            };
+            let file_info = remote_manifest.files.get(key);
+            let healthy = file_info
+                .and_then(FileInfo::stored_size)
+                .is_none_or(|expected| expected == *size);
+            let uploaded_at = file_info
+                .and_then(|file_info| file_info.last_upload.as_ref())
+                .map_or(i64::MIN, |upload| upload.uploaded_at);
+            let rank = (healthy, uploaded_at, Reverse(order));
+            if chosen.get(key).is_none_or(|(_, best)| rank > *best) {
+                chosen.insert(key.to_string(), (index, rank));
+            }
+        }
+    }
+    let is_chosen = |key: &str, index: usize| chosen.get(key).is_some_and(|(at, _)| *at == index);
+    for (index, (target, remote_files, remote_manifest)) in listings.iter_mut().enumerate() {
+        let key_prefix = format!("{}/", target.prefix);
+        remote_files.retain(|remote_key, _| {
+            remote_key
+                .strip_prefix(&key_prefix)
+                .is_some_and(|key| is_chosen(key, index))
+        });
+        remote_manifest.files.retain(|key, _| is_chosen(key, index));
+    }
+    Ok(listings)
+}
+
+struct DownloadPlan {
+    plan: SyncPlan,
+    /// Remote worlds the download checks out, whether or not they changed.
+    worlds: Vec<String>,
+    remote_manifest: UploadManifest,
+}
+
+async fn plan_download(destinations: &Destinations, folder_bucket: &str) -> Result<DownloadPlan, Error> {
+    let data_root = resolve_data_root()?;
+    let manifest = get_manifest_info()?;
+    let settings = manifest.folder_settings(folder_bucket);
+    let local_manifest = manifest.all_file_info.get(folder_bucket);
+
+    let copies = choose_copies(destinations).await?;
+    let found: usize = copies.iter().map(|(_, remote_files, _)| remote_files.len()).sum();
+    if found == 0 {
+        println!("No files found in cloud storage");
+        return Ok(DownloadPlan {
+            plan: SyncPlan::new(folder_bucket, SyncDirection::Download, Vec::new()),
+            worlds: Vec::new(),
+            remote_manifest: UploadManifest::default(),
+        });
+    }
+    
+    println!("Found {} files in cloud storage", found);
+    
+    // Determine which files need to be downloaded, scope by scope
+    let mut planned = Vec::new();
+    let mut worlds = Vec::new();
+    let mut remote_manifest = UploadManifest::default();
+    for (target, remote_files, target_manifest) in copies {
+        let prefix = &target.prefix;
+        for scope in settings.enabled_scopes() {
+            let scope_root = scope.local_root(&data_root)?;
+            let matcher = settings.filter(scope).compile()?;
+            if scope == SyncScope::Saves {
+                worlds.extend(
+                    remote_files
+                        .keys()
+                        .filter_map(|remote_key| remote_key.strip_prefix(&format!("{}/", prefix)))
+                        .filter(|key| FileInfo::is_world_key(key) && matcher.matches(key))
+                        .map(str::to_string),
+                );
+            }
+            planned.extend(determine_files_to_download(
+                &scope_root,
+                scope,
+                target,
+                &remote_files,
+                &matcher,
+                local_manifest,
+            ));
+        }
+        remote_manifest.files.extend(target_manifest.files);
+    }
+    let mut plan = SyncPlan::new(folder_bucket, SyncDirection::Download, planned);
+
+    // Overwriting a world the game has open corrupts it.
+    let processes = GameProcesses::scan();
+    let open_files: Vec<String> = plan
+        .transfers_and_conflicts()
+        .filter(|file| processes.holds_open(&file.local_path))
+        .map(|file| file.key.clone())
+        .collect();
+    if !open_files.is_empty() {
+        return Err(Error::Conflict(format!(
+            "Vintage Story has {} open; leave the world before downloading",
+            open_files.join(", ")
+        )));
+    }
+
+    worlds.sort();
+    let device = device_identity()?;
+    for (target, worlds) in destinations.by_primary(&worlds) {
+        check_leases(target, &worlds, &device).await?;
+    }
+
+    plan.warnings = download_warnings(&data_root, &plan, &remote_manifest)?;
+
+    Ok(DownloadPlan {
+        plan,
+        worlds,
+        remote_manifest,
+    })
+}
+
+/// Checks the worlds about to be downloaded against the installed game and
+/// the local Mods folder.
+fn download_warnings(
+    data_root: &Path,
+    plan: &SyncPlan,
+    remote_manifest: &UploadManifest,
+) -> Result<Vec<DownloadWarning>, Error> {
+    let mut warnings = Vec::new();
+    let mut installed = None;
+    let mut installed_version = None;
+
+    for file in plan.transfers_and_conflicts() {
+        let Some(file_info) = remote_manifest.files.get(&file.key) else {
+            continue;
+        };
+        if !FileInfo::is_world_key(&file.key) {
+            continue;
+        }
+
+        if let Some(saved_with) = file_info.game_version.as_deref().and_then(GameVersion::parse) {
+            let installed_version = installed_version.get_or_insert_with(|| {
+                let version = installed_game_version();
+                if version.is_none() {
+                    println!("Installed game version unknown; set VS_INSTALL_DIR to check worlds against it");
+                }
+                version
+            });
+            if let Some(installed) = installed_version.as_ref().filter(|installed| saved_with > **installed) {
+                warnings.push(DownloadWarning::NewerGameVersion {
+                    world: file_info.world_name.clone(),
+                    saved_with: saved_with.to_string(),
+                    installed: installed.to_string(),
+                });
+            }
+        }
+
+        if file_info.required_mods.is_empty() {
+            continue;
+        }
+        if installed.is_none() {
+            installed = Some(enabled_mods(data_root)?);
+        }
+        let installed = installed.as_deref().unwrap_or_default();
+        for mismatch in compare_mods(&file_info.required_mods, installed) {
+            warnings.push(DownloadWarning::Mods {
+                world: file_info.world_name.clone(),
+                mismatch,
+            });
+        }
+    }
+
+    Ok(warnings)
+}
+
+pub async fn run_download(
+    folder_bucket: &str,
+    progress: Option<ProgressCallback<'_>>,
+    options: DownloadOptions,
+) -> Result<(), Error> {
+    let destinations = connect(folder_bucket).await?;
+    let DownloadPlan { plan, worlds, remote_manifest } = plan_download(&destinations, folder_bucket).await?;
+
+    if !plan.warnings.is_empty() && !options.accept_warnings {
+        let details: Vec<String> = plan.warnings.iter().map(|w| w.to_string()).collect();
+        return Err(Error::NeedsConfirmation(format!(
+            "download needs confirmation: {}",
+            details.join("; ")
+        )));
+    }
+
+    plan.check_conflicts(&options.accepted_conflicts)?;
+
+    let files_to_download: Vec<&PlannedFile> = plan.transfers_accepting(&options.accepted_conflicts).collect();
+    if files_to_download.is_empty() {
+        // Up to date, but the worlds are about to be played here all the same.
+        check_out(&destinations, folder_bucket, &worlds).await?;
+        println!("All files are up to date. No download needed.");
+        return Ok(());
+    }
+    
+    let keys: Vec<&str> = files_to_download.iter().map(|file| file.key.as_str()).collect();
+    options.cancel.check()?;
+    run_pre_hook(HookStage::PreDownload, folder_bucket, keys.iter().copied())?;
+    let result = match check_out(&destinations, folder_bucket, &worlds).await {
+        Ok(()) => {
+            let total_bytes = files_to_download.iter().map(|file| file.size).sum();
+            let progress = ProgressReporter::new(progress, total_bytes);
+            fetch_files(&destinations, folder_bucket, &files_to_download, &remote_manifest, progress, &options.cancel).await
+        }
+        Err(err) => Err(err),
+    };
+    run_post_hook(HookStage::PostDownload, folder_bucket, keys, &result);
+    result
+}
+
+/// Downloads `files_to_download` and records them in the local manifest.
+/// A file that fails even after retries doesn't stop the others and is
+/// listed in the returned `Error::Incomplete`. Failed files and the one in
+/// flight when cancelled are left untouched; the files that finished are
+/// recorded either way.
+async fn fetch_files(
+    destinations: &Destinations,
+    folder_bucket: &str,
+    files_to_download: &[&PlannedFile],
+    remote_manifest: &UploadManifest,
+    mut progress: ProgressReporter<'_>,
+    cancel: &CancelToken,
+) -> Result<(), Error> {
+    println!("Downloading {} file(s)...", files_to_download.len());
+
+    let mut downloaded = Vec::new();
+    let mut failed: Vec<(String, Error)> = Vec::new();
+    let mut stopped = None;
+
+    progress.send("", false);
+    for file in files_to_download {
+        let PlannedFile { key: manifest_key, local_path, size: remote_size, .. } = *file;
+        let target = file
+            .profiles
+            .first()
+            .map_or_else(|| destinations.primary(manifest_key), |profile| destinations.target(profile));
+        match fetch_with_retry(target, file, &mut progress, cancel).await {
+            Ok(()) => {}
+            Err(err) if err.stops_transfer() => {
+                stopped = Some(err);
+                break;
+            }
+            Err(err) => {
+                failed.push((manifest_key.clone(), err));
+                continue;
+            }
+        }
+        progress.complete(0, manifest_key);
+
+        let file_info = remote_manifest.files.get(manifest_key).cloned().unwrap_or_else(|| FileInfo {
+            world_name: local_path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            file_size: Some(*remote_size),
+            ..FileInfo::default()
+        });
+        downloaded.push((manifest_key.clone(), file_info));
+    }
+
+    record_downloaded_files(folder_bucket, downloaded)?;
+    if let Some(err) = stopped {
+        return Err(err);
+    }
+    if !failed.is_empty() {
+        return Err(Error::Incomplete {
+            failed,
+            total: files_to_download.len(),
+        });
+    }
+
+    println!("Download complete!");
+    Ok(())
+}
+
+/// `fetch_file`, tried again on temporary failures. The bytes of a failed
+/// attempt are taken back out of the progress.
+async fn fetch_with_retry(
+    target: &RemoteTarget,
+    file: &PlannedFile,
+    progress: &mut ProgressReporter<'_>,
+    cancel: &CancelToken,
+) -> Result<(), Error> {
+    let what = format!("Downloading {}", file.key);
+    let mut backoff = Backoff::new(&target.retry);
+    loop {
+        let before = progress.transferred();
+        match cancel.run(fetch_file(&target.client, &target.bucket, file, progress)).await {
+            Ok(()) => return Ok(()),
+            Err(err) => {
+                progress.rewind(before);
+                cancel.run(backoff.wait(&what, err)).await?;
+            }
+        }
+    }
+}
+
+/// Downloads one file next to its local path, then moves it into place so
+/// an interrupted download never leaves half a file behind. The body is
+/// streamed to disk chunk by chunk, reporting each to `progress`.
+async fn fetch_file(
+    client: &Client,
+    bucket: &str,
+    file: &PlannedFile,
+    progress: &mut ProgressReporter<'_>,
+) -> Result<(), Error> {
+    let PlannedFile { remote_key, key, local_path, size: remote_size, .. } = file;
+    // Create parent directories if needed
+    if let Some(parent) = local_path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    println!("Downloading: {} -> {}", remote_key, local_path.display());
+
+    let response = client
+        .get_object()
+        .bucket(bucket)
+        .key(remote_key)
+        .send()
+        .await
+        .map_err(|err| Error::storage(format!("failed to download {}", remote_key), err))?;
+
+    let mut partial = local_path.as_os_str().to_os_string();
+    partial.push(".vscs-part");
+    let partial = TempFile(PathBuf::from(partial));
+    let mut output = fs::File::create(&partial.0)?;
+    let mut body = response.body;
+    let mut written = 0;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|err| Error::Network(format!("failed to read download body: {}", err)))?;
+        output.write_all(&chunk)?;
+        written += chunk.len() as u64;
+        progress.advance(chunk.len() as u64, key);
+    }
+    drop(output);
+    if written != *remote_size {
+        return Err(Error::Integrity(format!(
+            "{} arrived with {} of {} bytes",
+            remote_key, written, remote_size
+        )));
+    }
+    fs::rename(&partial.0, local_path)?;
+    note_download(local_path);
+
+    println!("Downloaded: {} ({} bytes)", local_path.display(), remote_size);
+    Ok(())
+}
+
+pub(crate) async fn list_remote_files(
+    client: &Client,
+    bucket: &str,
+    prefix: &str,
+    retry: &RetrySettings,
+) -> Result<HashMap<String, u64>, Error> {
+    let mut remote_files = HashMap::new();
+    let mut continuation_token: Option<String> = None;
+    
+    loop {
+        let mut request = client
+            .list_objects_v2()
+            .bucket(bucket)
+            .prefix(format!("{}/", prefix));
+        
+        if let Some(token) = continuation_token {
+            request = request.continuation_token(token);
+        }
+        
+        let response = with_retry(retry, "Listing the bucket", || async {
+            request
+                .clone()
+                .send()
+                .await
+                .map_err(|err| Error::storage("failed to list objects from bucket", err))
+        })
+        .await?;
+        
+        if let Some(contents) = response.contents.as_ref() {
+            for object in contents.iter() {
+                if let (Some(key), Some(size)) = (object.key(), object.size()) {
+                    remote_files.insert(key.to_string(), size as u64);
+                }
+            }
+        }
+        
+        continuation_token = response.next_continuation_token().map(|s| s.to_string());
+        if continuation_token.is_none() {
+            break;
+        }
+    }
+    
+    Ok(remote_files)
+}
+
+/// Plans the remote objects belonging to `scope`: those missing or
+/// differing locally under `scope_root` are downloaded, the rest skipped.
+/// A local copy that also changed since the last sync, according to
+/// `local_manifest`, is a conflict.
+fn determine_files_to_download(
+    scope_root: &Path,
+    scope: SyncScope,
+    target: &RemoteTarget,
+    remote_files: &HashMap<String, u64>,
+    matcher: &SyncMatcher,
+    local_manifest: Option<&UploadManifest>,
+) -> Vec<PlannedFile> {
+    let mut planned = Vec::new();
+
+    for (remote_key, remote_size) in remote_files {
+        // Remove the prefix to get the key relative to the folder bucket
+        let bucket_key = remote_key
+            .strip_prefix(&format!("{}/", target.prefix))
+            .unwrap_or(remote_key);
+        let (key_scope, relative_path) = SyncScope::split_key(bucket_key);
+
+        if is_meta_key(bucket_key) || key_scope != scope || !matcher.matches(relative_path) {
+            continue;
+        }
+
+        let local_path = scope_root.join(relative_path);
+        // Can't read metadata: download to be safe, as if it were missing.
+        let local_size = fs::metadata(&local_path).ok().map(|metadata| metadata.len());
+        let (action, reason) = match local_size {
+            None => (PlanAction::Download, PlanReason::MissingLocally),
+            Some(size) if size == *remote_size => (PlanAction::Skip, PlanReason::Unchanged),
+            Some(size) => {
+                let synced_size = local_manifest
+                    .and_then(|manifest| manifest.files.get(bucket_key))
+                    .and_then(|file_info| file_info.file_size);
+                if synced_size == Some(size) {
+                    (PlanAction::Download, PlanReason::RemoteDiffers)
+                } else {
+                    (PlanAction::Conflict, PlanReason::ChangedOnBothSides)
+                }
+            }
+        };
+
+        planned.push(PlannedFile {
+            key: bucket_key.to_string(),
+            action,
+            reason,
+            size: *remote_size,
+            local_path,
+            remote_key: remote_key.clone(),
+            profiles: vec![target.profile.clone()],
+        });
+    }
+
+    planned
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::manifest_info::DestinationStatus;
+    use crate::sync_filter::SyncFilter;
+
+    fn record(uploaded_at: i64) -> UploadRecord {
+        UploadRecord {
+            device_id: "device".to_string(),
+            device_name: "Device".to_string(),
+            uploaded_at,
+        }
+    }
+
+    fn synced(size: u64, uploaded_at: i64) -> FileInfo {
+        FileInfo {
+            file_size: Some(size),
+            last_upload: Some(record(uploaded_at)),
+            ..FileInfo::default()
+        }
+    }
+
+    fn profiles() -> Vec<String> {
+        vec!["main".to_string(), "mirror".to_string()]
+    }
+
+    #[test]
+    fn upload_of_a_new_file() {
+        let (action, reason, to) = classify_upload(None, None, 10, false, profiles());
+        assert_eq!((action, reason, to), (PlanAction::Upload, PlanReason::NewFile, profiles()));
+    }
+
+    #[test]
+    fn upload_of_a_file_changed_here() {
+        let last = synced(10, 1);
+        let (action, reason, _) = classify_upload(Some(&last), Some(&last), 12, false, profiles());
+        assert_eq!((action, reason), (PlanAction::Upload, PlanReason::SizeChanged));
+
+        let without_size = FileInfo { file_size: None, ..synced(10, 1) };
+        let (action, reason, _) = classify_upload(Some(&without_size), None, 10, false, profiles());
+        assert_eq!((action, reason), (PlanAction::Upload, PlanReason::NoSizeRecorded));
+    }
+
+    #[test]
+    fn skips_a_file_unchanged_everywhere() {
+        let mut last = synced(10, 1);
+        for profile in profiles() {
+            last.destinations.insert(
+                profile,
+                DestinationStatus {
+                    last_upload: last.last_upload.clone(),
+                    error: None,
+                },
+            );
+        }
+        let (action, reason, to) = classify_upload(Some(&last), Some(&last), 10, false, profiles());
+        assert_eq!((action, reason, to), (PlanAction::Skip, PlanReason::Unchanged, Vec::new()));
+    }
+
+    #[test]
+    fn catches_up_a_mirror_the_last_upload_missed() {
+        let mut last = synced(10, 1);
+        last.destinations.insert(
+            "main".to_string(),
+            DestinationStatus {
+                last_upload: last.last_upload.clone(),
+                error: None,
+            },
+        );
+        last.destinations.insert(
+            "mirror".to_string(),
+            DestinationStatus {
+                last_upload: None,
+                error: Some("unreachable".to_string()),
+            },
+        );
+        let (action, reason, to) = classify_upload(Some(&last), Some(&last), 10, false, profiles());
+        assert_eq!(
+            (action, reason, to),
+            (PlanAction::Upload, PlanReason::MissingFromProfile, vec!["mirror".to_string()])
+        );
+    }
+
+    #[test]
+    fn leaves_a_file_changed_only_in_the_cloud() {
+        let last = synced(10, 1);
+        let remote = synced(10, 2);
+        let (action, reason, _) = classify_upload(Some(&last), Some(&remote), 10, false, profiles());
+        assert_eq!((action, reason), (PlanAction::Skip, PlanReason::ChangedInCloud));
+    }
+
+    #[test]
+    fn a_file_changed_on_both_sides_is_a_conflict() {
+        let last = synced(10, 1);
+        let remote = synced(11, 2);
+        let (action, reason, _) = classify_upload(Some(&last), Some(&remote), 12, false, profiles());
+        assert_eq!((action, reason), (PlanAction::Conflict, PlanReason::ChangedOnBothSides));
+
+        // Another device uploaded the same name first.
+        let (action, _, _) = classify_upload(None, Some(&remote), 12, false, profiles());
+        assert_eq!(action, PlanAction::Conflict);
+        let (action, reason, _) = classify_upload(None, Some(&remote), 11, false, profiles());
+        assert_eq!((action, reason), (PlanAction::Upload, PlanReason::NewFile));
+    }
+
+    #[test]
+    fn a_requested_file_is_uploaded_unless_the_cloud_changed() {
+        let last = synced(10, 1);
+        let (action, reason, _) = classify_upload(Some(&last), Some(&last), 10, true, profiles());
+        assert_eq!((action, reason), (PlanAction::Upload, PlanReason::Requested));
+
+        let remote = synced(10, 2);
+        let (action, _, _) = classify_upload(Some(&last), Some(&remote), 10, true, profiles());
+        assert_eq!(action, PlanAction::Conflict);
+    }
+
+    fn target() -> RemoteTarget {
+        let config = aws_sdk_s3::Config::builder().behavior_version(BehaviorVersion::latest()).build();
+        RemoteTarget {
+            profile: "main".to_string(),
+            client: Client::from_conf(config),
+            bucket: "bucket".to_string(),
+            prefix: "world".to_string(),
+            encryption: Encryption::None,
+            retry: RetrySettings::default(),
+        }
+    }
+
+    #[test]
+    fn download_classification() {
+        let saves = tempfile::tempdir().unwrap();
+        fs::write(saves.path().join("same.vcdbs"), [0; 10]).unwrap();
+        fs::write(saves.path().join("behind.vcdbs"), [0; 10]).unwrap();
+        fs::write(saves.path().join("edited.vcdbs"), [0; 12]).unwrap();
+        let remote_files: HashMap<String, u64> = [
+            ("world/missing.vcdbs", 10),
+            ("world/same.vcdbs", 10),
+            ("world/behind.vcdbs", 20),
+            ("world/edited.vcdbs", 20),
+            ("world/_mods/mod.zip", 5),
+            ("world/same.vcdbs-journal", 1),
+        ]
+        .into_iter()
+        .map(|(key, size)| (key.to_string(), size))
+        .collect();
+        let local_manifest = UploadManifest {
+            files: [
+                ("behind.vcdbs".to_string(), synced(10, 1)),
+                ("edited.vcdbs".to_string(), synced(10, 1)),
+            ]
+            .into_iter()
+            .collect(),
+        };
+        let matcher = SyncFilter::default().compile().unwrap();
+
+        let mut planned = determine_files_to_download(
+            saves.path(),
+            SyncScope::Saves,
+            &target(),
+            &remote_files,
+            &matcher,
+            Some(&local_manifest),
+        );
+        planned.sort_by(|a, b| a.key.cmp(&b.key));
+        let classified: Vec<(&str, PlanAction, PlanReason)> = planned
+            .iter()
+            .map(|file| (file.key.as_str(), file.action, file.reason))
+            .collect();
+        assert_eq!(
+            classified,
+            [
+                ("behind.vcdbs", PlanAction::Download, PlanReason::RemoteDiffers),
+                ("edited.vcdbs", PlanAction::Conflict, PlanReason::ChangedOnBothSides),
+                ("missing.vcdbs", PlanAction::Download, PlanReason::MissingLocally),
+                ("same.vcdbs", PlanAction::Skip, PlanReason::Unchanged),
+            ]
+        );
+        assert_eq!(planned[0].local_path, saves.path().join("behind.vcdbs"));
+        assert_eq!(planned[0].remote_key, "world/behind.vcdbs");
+    }
+}
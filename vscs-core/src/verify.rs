@@ -0,0 +1,325 @@
+use rusqlite::{Connection, OpenFlags};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use tokio::runtime::Runtime;
+
+use crate::cancel::CancelToken;
+use crate::error::Error;
+use crate::manifest_info::FileInfo;
+use crate::progress::{ProgressCallback, ProgressReporter};
+use crate::remote_manifest::{fetch_remote_manifest, is_meta_key};
+use crate::retry::{with_retry, Backoff};
+use crate::upload_core::{connect, list_remote_files, RemoteTarget};
+
+/// How thoroughly `verify_backup` checks the stored objects.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct VerifyOptions {
+    /// Download every checked object and compare its hash, instead of only
+    /// asking the storage for its size and the hash stored with it.
+    pub download: bool,
+    /// Open downloaded worlds with SQLite and run its integrity check.
+    /// Worlds are downloaded for it even without `download`.
+    pub open_worlds: bool,
+    /// Check this many objects, picked at random, instead of all of them.
+    pub sample: Option<usize>,
+    #[serde(skip)]
+    pub cancel: CancelToken,
+}
+
+/// What is wrong with one object.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Problem {
+    /// In the manifest but not in the bucket.
+    Missing,
+    /// Not the size the manifest records.
+    WrongSize { expected: u64, actual: u64 },
+    /// The content doesn't match the hash recorded at upload.
+    WrongHash { expected: String, actual: String },
+    /// A world SQLite can't open or that fails its integrity check.
+    UnreadableWorld { error: String },
+    /// In the bucket but in no manifest, so no sync will ever use it.
+    Orphaned { size: u64 },
+    /// Couldn't be checked, e.g. the download kept failing.
+    Unchecked { error: String },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::Missing => write!(f, "missing"),
+            Problem::WrongSize { expected, actual } => {
+                write!(f, "corrupt: {} bytes instead of {}", actual, expected)
+            }
+            Problem::WrongHash { expected, actual } => {
+                write!(f, "corrupt: SHA-256 {} instead of {}", actual, expected)
+            }
+            Problem::UnreadableWorld { error } => write!(f, "world doesn't open: {}", error),
+            Problem::Orphaned { size } => write!(f, "orphaned ({} bytes, in no manifest)", size),
+            Problem::Unchecked { error } => write!(f, "not checked: {}", error),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    pub profile: String,
+    /// Manifest key, relative to the folder bucket.
+    pub key: String,
+    pub problem: Problem,
+}
+
+/// What `verify_backup` found in every profile a folder bucket is stored
+/// in.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct VerifyReport {
+    pub folder_bucket: String,
+    /// Objects checked, out of the `total` the manifests list.
+    pub checked: usize,
+    pub total: usize,
+    /// Checked objects uploaded before hashes were recorded; only their
+    /// size could be compared.
+    pub without_hash: usize,
+    pub findings: Vec<Finding>,
+}
+
+impl VerifyReport {
+    pub fn is_healthy(&self) -> bool {
+        self.findings.is_empty()
+    }
+}
+
+impl fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Verified {} of {} objects in {}: {} problem{}",
+            self.checked,
+            self.total,
+            self.folder_bucket,
+            self.findings.len(),
+            if self.findings.len() == 1 { "" } else { "s" }
+        )?;
+        for finding in &self.findings {
+            writeln!(f, "  {}: {}: {}", finding.profile, finding.key, finding.problem)?;
+        }
+        if self.without_hash > 0 {
+            writeln!(
+                f,
+                "  {} objects have no recorded hash; upload them again to check their content",
+                self.without_hash
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Checks that what the manifests of `folder` list is in its buckets and
+/// intact, and that nothing else is. Changes nothing; finding problems is
+/// not an error.
+pub fn verify_backup(
+    folder: String,
+    options: VerifyOptions,
+    progress: Option<ProgressCallback<'_>>,
+) -> Result<VerifyReport, Error> {
+    let rt = Runtime::new()?;
+    rt.block_on(run_verify(&folder, options, progress))
+}
+
+/// An object the manifest in one profile lists.
+struct Check<'a> {
+    target: &'a RemoteTarget,
+    key: String,
+    file_info: FileInfo,
+}
+
+pub async fn run_verify(
+    folder_bucket: &str,
+    options: VerifyOptions,
+    progress: Option<ProgressCallback<'_>>,
+) -> Result<VerifyReport, Error> {
+    let destinations = connect(folder_bucket).await?;
+    let mut report = VerifyReport {
+        folder_bucket: folder_bucket.to_string(),
+        ..VerifyReport::default()
+    };
+
+    let mut checks = Vec::new();
+    for target in &destinations.targets {
+        let RemoteTarget { client, bucket, prefix, retry, .. } = target;
+        let remote_manifest = fetch_remote_manifest(client, bucket, prefix, retry).await?;
+        let key_prefix = format!("{}/", prefix);
+        for (remote_key, size) in list_remote_files(client, bucket, prefix, retry).await? {
+            let Some(key) = remote_key.strip_prefix(&key_prefix) else {
+                continue;
+            };
+            if !is_meta_key(key) && !remote_manifest.files.contains_key(key) {
+                report.findings.push(Finding {
+                    profile: target.profile.clone(),
+                    key: key.to_string(),
+                    problem: Problem::Orphaned { size },
+                });
+            }
+        }
+        checks.extend(
+            remote_manifest
+                .files
+                .into_iter()
+                .map(|(key, file_info)| Check { target, key, file_info }),
+        );
+    }
+    report.total = checks.len();
+    if let Some(sample) = options.sample {
+        fastrand::shuffle(&mut checks);
+        checks.truncate(sample);
+    }
+    checks.sort_by(|a, b| (&a.target.profile, &a.key).cmp(&(&b.target.profile, &b.key)));
+    report.checked = checks.len();
+
+    let downloads = |check: &Check| options.download || (options.open_worlds && FileInfo::is_world_key(&check.key));
+    let total_bytes = checks
+        .iter()
+        .filter(|check| downloads(check))
+        .filter_map(|check| check.file_info.stored_size())
+        .sum();
+    let mut progress = ProgressReporter::new(progress, total_bytes);
+    progress.send("", false);
+
+    for check in &checks {
+        options.cancel.check()?;
+        if check.file_info.sha256.is_none() {
+            report.without_hash += 1;
+        }
+        let result = verify_object(check, downloads(check), options.open_worlds, &mut progress, &options.cancel).await;
+        let problem = match result {
+            Ok(problem) => problem,
+            Err(err) if err.stops_transfer() => return Err(err),
+            Err(err) => Some(Problem::Unchecked { error: err.to_string() }),
+        };
+        if let Some(problem) = problem {
+            report.findings.push(Finding {
+                profile: check.target.profile.clone(),
+                key: check.key.clone(),
+                problem,
+            });
+        }
+    }
+    Ok(report)
+}
+
+/// Checks one object: asks the storage for its size and stored hash, then
+/// with `download` fetches it to hash its content and, for a world with
+/// `open_worlds`, opens it with SQLite.
+async fn verify_object(
+    check: &Check<'_>,
+    download: bool,
+    open_worlds: bool,
+    progress: &mut ProgressReporter<'_>,
+    cancel: &CancelToken,
+) -> Result<Option<Problem>, Error> {
+    let Check { target, key, file_info } = check;
+    let remote_key = target.remote_key(key);
+    let head = with_retry(&target.retry, &format!("Checking {}", remote_key), || async {
+        match target.client.head_object().bucket(&target.bucket).key(&remote_key).send().await {
+            Ok(head) => Ok(Some(head)),
+            Err(err) if err.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(err) => Err(Error::storage(format!("failed to check {}", remote_key), err)),
+        }
+    })
+    .await?;
+    let Some(head) = head else {
+        return Ok(Some(Problem::Missing));
+    };
+
+    let size = head.content_length().unwrap_or_default() as u64;
+    if let Some(expected) = file_info.stored_size().filter(|expected| *expected != size) {
+        return Ok(Some(Problem::WrongSize { expected, actual: size }));
+    }
+    if !download {
+        let stored_hash = head.metadata().and_then(|metadata| metadata.get("sha256"));
+        return Ok(match (&file_info.sha256, stored_hash) {
+            (Some(expected), Some(actual)) if expected != actual => Some(Problem::WrongHash {
+                expected: expected.clone(),
+                actual: actual.clone(),
+            }),
+            _ => None,
+        });
+    }
+
+    // Created exclusively, so nothing already at the path is written through.
+    let local = tempfile::Builder::new().prefix("vscs-verify-").tempfile()?.into_temp_path();
+    let what = format!("Downloading {}", remote_key);
+    let mut backoff = Backoff::new(&target.retry);
+    let actual = loop {
+        let before = progress.transferred();
+        match cancel.run(download_object(target, &remote_key, &local, key, progress)).await {
+            Ok(hash) => break hash,
+            Err(err) => {
+                progress.rewind(before);
+                cancel.run(backoff.wait(&what, err)).await?;
+            }
+        }
+    };
+    progress.complete(0, key);
+
+    if let Some(expected) = file_info.sha256.as_ref().filter(|expected| **expected != actual) {
+        return Ok(Some(Problem::WrongHash {
+            expected: expected.clone(),
+            actual,
+        }));
+    }
+    if open_worlds && FileInfo::is_world_key(key) {
+        return Ok(check_world(&local).map(|error| Problem::UnreadableWorld { error }));
+    }
+    Ok(None)
+}
+
+/// Downloads `remote_key` of `target` to `path`, returning the SHA-256 of
+/// what arrived.
+async fn download_object(
+    target: &RemoteTarget,
+    remote_key: &str,
+    path: &Path,
+    key: &str,
+    progress: &mut ProgressReporter<'_>,
+) -> Result<String, Error> {
+    let response = target
+        .client
+        .get_object()
+        .bucket(&target.bucket)
+        .key(remote_key)
+        .send()
+        .await
+        .map_err(|err| Error::storage(format!("failed to download {}", remote_key), err))?;
+
+    let mut output = fs::File::create(path)?;
+    let mut hasher = Sha256::new();
+    let mut body = response.body;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|err| Error::Network(format!("failed to read download body: {}", err)))?;
+        output.write_all(&chunk)?;
+        hasher.update(&chunk);
+        progress.advance(chunk.len() as u64, key);
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Opens the world at `path` read-only and runs SQLite's quick integrity
+/// check, returning what is wrong with it.
+fn check_world(path: &Path) -> Option<String> {
+    let result = Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .and_then(|connection| connection.query_row("PRAGMA quick_check", [], |row| row.get::<_, String>(0)));
+    match result {
+        Ok(status) if status == "ok" => None,
+        Ok(status) => Some(status),
+        Err(err) => Some(err.to_string()),
+    }
+}
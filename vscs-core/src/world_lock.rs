@@ -0,0 +1,309 @@
+use aws_sdk_s3::primitives::ByteStream;
+use chrono::{Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::device::DeviceIdentity;
+use crate::error::Error;
+use crate::remote_manifest::REMOTE_META_DIR;
+use crate::retry::with_retry;
+use crate::upload_core::{renew_checkouts, RemoteTarget};
+
+/// Granularity at which the lease keeper thread notices it should stop.
+const STOP_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// A device's claim on a shared world, stored in the bucket next to the
+/// shared manifest. It is taken when the world is downloaded ("checked
+/// out"), renewed while the app runs and dropped when the world is uploaded
+/// after the game let go of it ("checked in"). A lease that was not renewed
+/// in time is stale and any device may break it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WorldLease {
+    pub device_id: String,
+    pub device_name: String,
+    /// Vintage Story player name on that device.
+    pub player: String,
+    /// Unix time the world was checked out.
+    pub acquired_at: i64,
+    /// Unix time after which the lease is stale.
+    pub expires_at: i64,
+}
+
+impl WorldLease {
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at <= now
+    }
+
+    fn held_by_other(&self, device: &DeviceIdentity, now: i64) -> bool {
+        self.device_id != device.id && !self.is_expired(now)
+    }
+
+    pub fn describe(&self, world: &str) -> String {
+        let since = Local
+            .timestamp_opt(self.acquired_at, 0)
+            .single()
+            .map(|time| time.format("%Y-%m-%d %H:%M").to_string())
+            .unwrap_or_else(|| "an unknown time".to_string());
+        format!(
+            "{} is currently checked out by {} on {} since {}",
+            world, self.player, self.device_name, since
+        )
+    }
+}
+
+fn lock_key(prefix: &str, world: &str) -> String {
+    format!("{}/{}/locks/{}.json", prefix, REMOTE_META_DIR, world)
+}
+
+fn now() -> i64 {
+    Local::now().timestamp()
+}
+
+/// Player name for leases: the one in clientsettings.json, or the device
+/// name when there is none (e.g. on a dedicated server).
+pub fn player_name(data_root: &Path, device: &DeviceIdentity) -> String {
+    fs::read_to_string(data_root.join("clientsettings.json"))
+        .ok()
+        .and_then(|data| serde_json::from_str::<serde_json::Value>(&data).ok())
+        .and_then(|settings| {
+            settings
+                .pointer("/stringSettings/playername")
+                .and_then(|name| name.as_str())
+                .map(str::to_string)
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| device.name.clone())
+}
+
+async fn fetch_lease(target: &RemoteTarget, key: &str) -> Result<Option<WorldLease>, Error> {
+    let body = with_retry(&target.retry, &format!("Fetching lock {}", key), || async {
+        let response = match target.client.get_object().bucket(&target.bucket).key(key).send().await {
+            Ok(response) => response,
+            Err(err) if err.as_service_error().is_some_and(|e| e.is_no_such_key()) => return Ok(None),
+            Err(err) => return Err(Error::storage(format!("failed to fetch lock {}", key), err)),
+        };
+        let body = response
+            .body
+            .collect()
+            .await
+            .map_err(|err| Error::Network(format!("failed to read lock {}: {}", key, err)))?;
+        Ok(Some(body.to_vec()))
+    })
+    .await?;
+    // An unreadable lock protects nothing; treat it as absent.
+    Ok(body.and_then(|body| serde_json::from_slice(&body).ok()))
+}
+
+async fn store_lease(target: &RemoteTarget, key: &str, lease: &WorldLease) -> Result<(), Error> {
+    let data = serde_json::to_vec_pretty(lease)
+        .map_err(|err| Error::other(format!("failed to serialize lock: {}", err)))?;
+    with_retry(&target.retry, &format!("Storing lock {}", key), || async {
+        let request = target
+            .client
+            .put_object()
+            .bucket(&target.bucket)
+            .key(key)
+            .content_type("application/json")
+            .body(ByteStream::from(data.clone()));
+        target
+            .encryption
+            .apply(request)
+            .send()
+            .await
+            .map_err(|err| Error::storage(format!("failed to store lock {}", key), err))
+    })
+    .await?;
+    Ok(())
+}
+
+/// Fails when another device has one of `worlds` checked out.
+pub(crate) async fn check_leases(
+    target: &RemoteTarget,
+    worlds: &[String],
+    device: &DeviceIdentity,
+) -> Result<(), Error> {
+    let now = now();
+    for world in worlds {
+        let lease = fetch_lease(target, &lock_key(&target.prefix, world)).await?;
+        if let Some(lease) = lease.filter(|lease| lease.held_by_other(device, now)) {
+            return Err(Error::Conflict(lease.describe(world)));
+        }
+    }
+    Ok(())
+}
+
+/// Checks `worlds` out to this device for `lease`, breaking stale leases of
+/// other devices. Renewing a world this device already holds keeps its
+/// original checkout time.
+pub(crate) async fn acquire_leases(
+    target: &RemoteTarget,
+    worlds: &[String],
+    device: &DeviceIdentity,
+    player: &str,
+    lease: Duration,
+) -> Result<(), Error> {
+    let now = now();
+    let expires_at = now + i64::try_from(lease.as_secs()).unwrap_or(i64::MAX / 2);
+
+    for world in worlds {
+        let key = lock_key(&target.prefix, world);
+        let existing = fetch_lease(target, &key).await?;
+        if let Some(existing) = existing.as_ref().filter(|existing| existing.device_id != device.id) {
+            if !existing.is_expired(now) {
+                return Err(Error::Conflict(existing.describe(world)));
+            }
+            println!("Breaking stale lock: {}", existing.describe(world));
+        }
+
+        let acquired_at = existing
+            .filter(|existing| existing.device_id == device.id)
+            .map_or(now, |existing| existing.acquired_at);
+        let lease = WorldLease {
+            device_id: device.id.clone(),
+            device_name: device.name.clone(),
+            player: player.to_string(),
+            acquired_at,
+            expires_at,
+        };
+        store_lease(target, &key, &lease).await?;
+
+        // The bucket has no conditional writes; reading the lock back
+        // catches a device that took it at the same moment.
+        let stored = fetch_lease(target, &key).await?;
+        if let Some(stored) = stored.filter(|stored| stored.device_id != device.id) {
+            return Err(Error::Conflict(stored.describe(world)));
+        }
+    }
+    Ok(())
+}
+
+/// Extends the leases this device holds on `worlds`. Returns the worlds
+/// whose lease is gone or was taken over by another device.
+pub(crate) async fn renew_leases(
+    target: &RemoteTarget,
+    worlds: &[String],
+    device: &DeviceIdentity,
+    lease: Duration,
+) -> Result<Vec<String>, Error> {
+    let expires_at = now() + i64::try_from(lease.as_secs()).unwrap_or(i64::MAX / 2);
+    let mut lost = Vec::new();
+    for world in worlds {
+        let key = lock_key(&target.prefix, world);
+        match fetch_lease(target, &key).await? {
+            Some(held) if held.device_id == device.id => {
+                store_lease(target, &key, &WorldLease { expires_at, ..held }).await?;
+            }
+            _ => lost.push(world.clone()),
+        }
+    }
+    Ok(lost)
+}
+
+/// Checks `worlds` back in, deleting the leases this device holds on them.
+pub(crate) async fn release_leases(
+    target: &RemoteTarget,
+    worlds: &[String],
+    device: &DeviceIdentity,
+) -> Result<(), Error> {
+    for world in worlds {
+        let key = lock_key(&target.prefix, world);
+        let held = fetch_lease(target, &key).await?;
+        if held.is_some_and(|held| held.device_id == device.id) {
+            with_retry(&target.retry, &format!("Releasing lock {}", key), || async {
+                target
+                    .client
+                    .delete_object()
+                    .bucket(&target.bucket)
+                    .key(&key)
+                    .send()
+                    .await
+                    .map_err(|err| Error::storage(format!("failed to release lock {}", key), err))
+            })
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+/// Background thread renewing the leases of the worlds this device has
+/// checked out, so they only go stale once the app stops running. Stops
+/// when dropped.
+pub struct LeaseKeeper {
+    stop: Arc<AtomicBool>,
+}
+
+impl LeaseKeeper {
+    pub fn start(lease: Duration) -> LeaseKeeper {
+        let stop = Arc::new(AtomicBool::new(false));
+        {
+            let stop = stop.clone();
+            // Renew well before expiry so one failed attempt is not fatal.
+            let interval = lease / 3;
+            thread::spawn(move || {
+                let mut next_renewal = Instant::now();
+                while !stop.load(Ordering::Relaxed) {
+                    if Instant::now() < next_renewal {
+                        thread::sleep(STOP_CHECK_INTERVAL);
+                        continue;
+                    }
+                    next_renewal = Instant::now() + interval;
+                    if let Err(err) = renew_checkouts() {
+                        println!("Failed to renew world locks: {}", err);
+                    }
+                }
+            });
+        }
+        LeaseKeeper { stop }
+    }
+}
+
+impl Drop for LeaseKeeper {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn device(id: &str) -> DeviceIdentity {
+        DeviceIdentity {
+            id: id.to_string(),
+            name: format!("{} pc", id),
+        }
+    }
+
+    fn lease(device_id: &str, expires_at: i64) -> WorldLease {
+        WorldLease {
+            device_id: device_id.to_string(),
+            device_name: format!("{} pc", device_id),
+            player: "Tyron".to_string(),
+            acquired_at: 0,
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn another_device_holds_a_lease_until_it_expires() {
+        let theirs = lease("desktop", 100);
+        assert!(theirs.held_by_other(&device("laptop"), 99));
+        assert!(!theirs.held_by_other(&device("laptop"), 100));
+        assert!(!theirs.held_by_other(&device("laptop"), 500));
+    }
+
+    #[test]
+    fn a_device_never_blocks_itself() {
+        assert!(!lease("laptop", 100).held_by_other(&device("laptop"), 50));
+    }
+
+    #[test]
+    fn leases_live_in_the_meta_directory() {
+        assert_eq!(lock_key("team/server", "world.vcdbs"), "team/server/.vscs/locks/world.vcdbs.json");
+    }
+}